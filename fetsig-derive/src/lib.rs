@@ -0,0 +1,122 @@
+use proc_macro::TokenStream;
+use proc_macro2::Ident;
+use quote::quote;
+use syn::{Data, DeriveInput, Error, Fields, parse_macro_input};
+
+// field marked `#[dirty]`, or named `dirty`
+#[proc_macro_derive(Dirty, attributes(dirty))]
+pub fn derive_dirty(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let field = match flag_field(&input, "dirty", "dirty") {
+        Ok(field) => field,
+        Err(error) => return error.to_compile_error().into(),
+    };
+
+    let name = &input.ident;
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+    quote! {
+        impl #impl_generics ::fetsig::Dirty for #name #type_generics #where_clause {
+            fn is_dirty(&self) -> bool {
+                self.#field
+            }
+
+            fn take_dirty(&mut self) -> bool {
+                ::core::mem::take(&mut self.#field)
+            }
+
+            fn with_dirty(mut self) -> Self {
+                self.#field = true;
+                self
+            }
+
+            fn mark_as_dirty(&mut self) {
+                self.#field = true;
+            }
+        }
+    }
+    .into()
+}
+
+// field marked `#[new]`, or named `is_new`
+#[proc_macro_derive(New, attributes(new))]
+pub fn derive_new(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let field = match flag_field(&input, "new", "is_new") {
+        Ok(field) => field,
+        Err(error) => return error.to_compile_error().into(),
+    };
+
+    let name = &input.ident;
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+    quote! {
+        impl #impl_generics ::fetsig::New for #name #type_generics #where_clause {
+            fn is_new(&self) -> bool {
+                self.#field
+            }
+
+            fn with_new(mut self) -> Self {
+                self.#field = true;
+                self
+            }
+
+            fn with_existing(mut self) -> Self {
+                self.#field = false;
+                self
+            }
+        }
+    }
+    .into()
+}
+
+// field marked by the attribute wins over the field of the default name
+fn flag_field<'a>(
+    input: &'a DeriveInput,
+    attribute: &str,
+    default_name: &str,
+) -> Result<&'a Ident, Error> {
+    let Data::Struct(data) = &input.data else {
+        return Err(Error::new_spanned(
+            &input.ident,
+            "only structs with named fields are supported",
+        ));
+    };
+    let Fields::Named(fields) = &data.fields else {
+        return Err(Error::new_spanned(
+            &input.ident,
+            "only structs with named fields are supported",
+        ));
+    };
+
+    let marked = fields
+        .named
+        .iter()
+        .filter(|field| {
+            field
+                .attrs
+                .iter()
+                .any(|attr| attr.path().is_ident(attribute))
+        })
+        .collect::<Vec<_>>();
+    let field = match marked.as_slice() {
+        [field] => Some(*field),
+        [] => fields.named.iter().find(|field| {
+            field
+                .ident
+                .as_ref()
+                .is_some_and(|ident| ident == default_name)
+        }),
+        [_, field, ..] => {
+            return Err(Error::new_spanned(
+                field,
+                format!("only one field can be marked #[{attribute}]"),
+            ));
+        }
+    };
+
+    field.and_then(|field| field.ident.as_ref()).ok_or_else(|| {
+        Error::new_spanned(
+            &input.ident,
+            format!("mark a bool field #[{attribute}] or name it `{default_name}`"),
+        )
+    })
+}
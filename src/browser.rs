@@ -1,7 +1,16 @@
+use std::fmt::{self, Display, Formatter};
+
+#[cfg(feature = "browser")]
+use artwrap::spawn_local;
+#[cfg(feature = "browser")]
 use js_sys::Error;
-use smol_str::{SmolStr, format_smolstr};
+use smol_str::{SmolStr, ToSmolStr};
+#[cfg(feature = "browser")]
 use wasm_bindgen::JsValue;
 
+mod batch;
+pub use batch::*;
+
 mod collection;
 pub use collection::*;
 
@@ -10,26 +19,113 @@ pub use collectionstate::*;
 
 mod common;
 pub use common::{
-    DecodeMode, DeserializeMode, FetchDeserializable, decode_content, deserialize_content, none,
+    Abort, DecodeMode, DeserializeMode, FetchDeserializable, FetchHandle, ResponseHeaders, none,
 };
+#[cfg(feature = "browser")]
+pub use common::{decode_content, deserialize_content};
 
 mod entity;
 pub use self::entity::*;
 
+#[cfg(feature = "browser")]
 mod file;
+#[cfg(feature = "browser")]
 pub use file::*;
 
+mod join;
+pub use join::*;
+
 mod mac;
 pub use mac::*;
 
+#[cfg(not(feature = "browser"))]
+mod native;
+#[cfg(not(feature = "browser"))]
+pub use native::block_on;
+#[cfg(not(feature = "browser"))]
+use native::spawn_local;
+
 mod request;
 pub use request::*;
 
+#[cfg(all(feature = "browser", feature = "json"))]
+mod sse;
+#[cfg(all(feature = "browser", feature = "json"))]
+pub use sse::*;
+
 mod transferstate;
+pub use transferstate::{OperationState, TransferState};
 
 mod upload;
 pub use upload::*;
 
-fn js_error(value: impl Into<JsValue>) -> SmolStr {
-    format_smolstr!("{}", Error::from(value.into()).to_string())
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FetchError {
+    name: SmolStr,
+    message: SmolStr,
+}
+
+impl FetchError {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+// same as `Error.prototype.toString()`
+impl Display for FetchError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match (self.name.is_empty(), self.message.is_empty()) {
+            (false, false) => write!(f, "{}: {}", self.name, self.message),
+            (false, true) => f.write_str(&self.name),
+            (true, _) => f.write_str(&self.message),
+        }
+    }
+}
+
+impl From<FetchError> for SmolStr {
+    fn from(error: FetchError) -> Self {
+        error.to_smolstr()
+    }
+}
+
+#[cfg(feature = "browser")]
+fn js_error(value: impl Into<JsValue>) -> FetchError {
+    let error = Error::from(value.into());
+    let name = error.name().as_string().unwrap_or_default();
+    let message = error.message().as_string().unwrap_or_default();
+    if name.is_empty() && message.is_empty() {
+        // thrown value is not an Error
+        FetchError {
+            name: SmolStr::default(),
+            message: error.to_string().as_string().unwrap_or_default().into(),
+        }
+    } else {
+        FetchError {
+            name: name.into(),
+            message: message.into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fetch_error_displays_as_js() {
+        let error = |name: &str, message: &str| FetchError {
+            name: name.into(),
+            message: message.into(),
+        };
+        assert_eq!(
+            "TypeError: Failed to fetch",
+            error("TypeError", "Failed to fetch").to_string()
+        );
+        assert_eq!("AbortError", error("AbortError", "").to_string());
+        assert_eq!("thrown", error("", "thrown").to_string());
+    }
 }
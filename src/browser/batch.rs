@@ -0,0 +1,234 @@
+use std::{marker::PhantomData, rc::Rc};
+
+use futures_signals::signal::{Mutable, Signal, SignalExt};
+use futures_signals_ext::{MutableExt, MutableOption};
+use log::{debug, error, warn};
+use serde::Serialize;
+use smol_str::{SmolStr, ToSmolStr};
+
+#[cfg(feature = "cbor")]
+use crate::CborSerialize;
+#[cfg(feature = "json")]
+use crate::JSONSerialize;
+#[cfg(feature = "messagepack")]
+use crate::MsgPackSerialize;
+#[cfg(feature = "postcard")]
+use crate::PostcardSerialize;
+use crate::{
+    BatchRequest, BatchResponse, Dirty, HEADER_SIGNATURE, MacSign, MacVerify, MediaType, Messages,
+    NoMac, StatusCode,
+};
+
+use super::{
+    EntityStore, common::FetchHandle, fetch, request::Request, transferstate::TransferState,
+};
+
+type EntitySerializer = Box<dyn Fn(MediaType) -> Result<Vec<u8>, SmolStr>>;
+
+struct BatchEntry {
+    key: SmolStr,
+    serialize: EntitySerializer,
+    take_dirty: Rc<dyn Fn()>,
+    transfer_state: Mutable<TransferState>,
+    messages: Messages,
+}
+
+pub struct BatchStore<MV = NoMac> {
+    transfer_state: Mutable<TransferState>,
+    transfer_generation: Mutable<u64>,
+    messages: Messages,
+    entries: Vec<BatchEntry>,
+    pmv: PhantomData<MV>,
+}
+
+impl<MV> BatchStore<MV> {
+    pub fn new() -> Self {
+        Self {
+            transfer_state: Mutable::new(TransferState::Empty),
+            transfer_generation: Mutable::new(0),
+            messages: Messages::new(),
+            entries: Vec::new(),
+            pmv: PhantomData,
+        }
+    }
+
+    #[must_use]
+    pub fn with_entity<E, MV2>(mut self, key: impl ToSmolStr, store: &EntityStore<E, MV2>) -> Self
+    where
+        E: Dirty + Serialize + 'static,
+    {
+        let entity = store.entity().clone();
+        self.entries.push(BatchEntry {
+            key: key.to_smolstr(),
+            serialize: Box::new({
+                let entity = entity.clone();
+                move |media_type| match &*entity.lock_ref() {
+                    Some(entity) => serialize(entity, media_type),
+                    None => Err(SmolStr::new_static("Cannot store nonexisting entity")),
+                }
+            }),
+            take_dirty: Rc::new(move || {
+                if let Some(entity) = entity.lock_mut().as_mut() {
+                    entity.take_dirty();
+                }
+            }),
+            transfer_state: store.transfer_state().clone(),
+            messages: store.messages().clone(),
+        });
+        self
+    }
+
+    // messages not related to any batched entity
+    pub fn messages(&self) -> &Messages {
+        &self.messages
+    }
+
+    pub fn transfer_state(&self) -> &Mutable<TransferState> {
+        &self.transfer_state
+    }
+
+    pub fn pending(&self) -> bool {
+        self.transfer_state.map(TransferState::pending)
+    }
+
+    fn entries_pending(&self) -> bool {
+        self.entries
+            .iter()
+            .any(|entry| entry.transfer_state.map(TransferState::pending))
+    }
+
+    pub fn pending_signal(&self) -> impl Signal<Item = bool> + use<MV> {
+        self.transfer_state.signal_ref(|state| state.pending())
+    }
+
+    pub fn stored_status(&self) -> Option<StatusCode> {
+        self.transfer_state.map(TransferState::stored_status)
+    }
+
+    pub fn stored_status_signal(&self) -> impl Signal<Item = Option<StatusCode>> + use<MV> {
+        self.transfer_state
+            .signal_ref(TransferState::stored_status)
+            .dedupe()
+    }
+}
+
+impl<MV> BatchStore<MV>
+where
+    MV: MacVerify,
+{
+    pub fn store<MS, C>(&self, request: Request<'_>, result_callback: C) -> FetchHandle
+    where
+        MS: MacSign,
+        C: FnOnce(StatusCode) + 'static,
+    {
+        let mut request = request.with_is_load(false);
+        if request.logging() {
+            debug!(
+                "Request to store batch of {} entities to {}",
+                self.entries.len(),
+                request.url()
+            );
+        }
+
+        if self.entries_pending() {
+            if request.logging() {
+                debug!(
+                    "Request to {} skipped, a batched store is pending",
+                    request.url()
+                );
+            }
+            result_callback(StatusCode::Undefined);
+            return FetchHandle::completed();
+        }
+
+        let media_type = request.media_type().unwrap_or_default();
+        let mut batch = BatchRequest::new();
+        for entry in &self.entries {
+            match (entry.serialize)(media_type) {
+                Ok(bytes) => batch = batch.with_entity(entry.key.clone(), bytes),
+                Err(error) => {
+                    if request.logging() {
+                        warn!("Cannot serialize batch entity {}: {error}", entry.key);
+                    }
+                    self.messages.replace(Messages::from_service_error(error));
+                    result_callback(StatusCode::BadRequest);
+                    return FetchHandle::completed();
+                }
+            }
+        }
+        let bytes = match serialize(&batch, media_type) {
+            Ok(bytes) => bytes,
+            Err(error) => {
+                if request.logging() {
+                    error!("Cannot serialize batch: {error}");
+                }
+                self.messages.replace(Messages::from_service_error(error));
+                result_callback(StatusCode::BadRequest);
+                return FetchHandle::completed();
+            }
+        };
+        if let Some(signature) = MS::sign(bytes.as_ref()) {
+            request = request.with_header(HEADER_SIGNATURE, signature);
+        }
+        request = request.with_body(bytes);
+
+        let response = MutableOption::<BatchResponse>::new(None);
+        let targets = self
+            .entries
+            .iter()
+            .map(|entry| {
+                (
+                    entry.key.clone(),
+                    entry.messages.clone(),
+                    entry.transfer_state.clone(),
+                    entry.take_dirty.clone(),
+                )
+            })
+            .collect::<Vec<_>>();
+        fetch::<_, _, MV>(
+            request,
+            self.transfer_state.clone(),
+            self.transfer_generation.clone(),
+            self.messages.clone(),
+            Some(response.clone()),
+            move |status, _| {
+                let mut messages = response.replace(None).map(BatchResponse::take);
+                for (key, target, transfer_state, take_dirty) in targets {
+                    if let Some(messages) = messages.as_mut() {
+                        target.replace(messages.remove(&key).unwrap_or_else(Messages::new));
+                    }
+                    if status.is_success() {
+                        take_dirty();
+                        transfer_state.set(TransferState::Stored(status));
+                    }
+                }
+                result_callback(status)
+            },
+        )
+    }
+}
+
+impl<MV> Default for BatchStore<MV> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn serialize<T>(value: &T, media_type: MediaType) -> Result<Vec<u8>, SmolStr>
+where
+    T: Serialize,
+{
+    match media_type {
+        #[cfg(feature = "cbor")]
+        MediaType::Cbor => value.to_cbor(),
+        #[cfg(feature = "json")]
+        MediaType::Json => value.to_json(),
+        #[cfg(feature = "messagepack")]
+        MediaType::MsgPack => value.to_msgpack(),
+        #[cfg(feature = "postcard")]
+        MediaType::Postcard => value.to_postcard(),
+        _ => Err(SmolStr::new_static(
+            "Request failed as unsupported media type is requested",
+        )),
+    }
+}
@@ -1,6 +1,7 @@
-use std::marker::PhantomData;
+use std::{
+    cell::RefCell, cmp::Ordering, collections::HashSet, hash::Hash, marker::PhantomData, rc::Rc,
+};
 
-use artwrap::spawn_local;
 use futures_signals::{
     map_ref,
     signal::{Mutable, Signal, SignalExt},
@@ -11,21 +12,35 @@ use futures_signals::{
 use futures_signals_ext::{MutableExt, MutableVecExt};
 use log::{debug, error, trace, warn};
 use serde::{Serialize, de::DeserializeOwned};
-
-#[cfg(feature = "json")]
-use crate::JSONSerialize;
-#[cfg(any(feature = "json", feature = "postcard"))]
+use smol_str::SmolStr;
+
+#[cfg(feature = "cbor")]
+use crate::CborSerialize;
+#[cfg(any(
+    feature = "cbor",
+    feature = "json",
+    feature = "messagepack",
+    feature = "postcard"
+))]
 use crate::MediaType;
+#[cfg(feature = "messagepack")]
+use crate::MsgPackSerialize;
 #[cfg(feature = "postcard")]
 use crate::PostcardSerialize;
 use crate::{
-    CollectionResponse, HEADER_SIGNATURE, MacSign, MacVerify, Messages, NoMac, Paging, StatusCode,
+    CollectionResponse, Dirty, HEADER_SIGNATURE, MacSign, MacVerify, Messages, NoMac, Paging,
+    StatusCode,
 };
+#[cfg(feature = "json")]
+use crate::{HEADER_ACCEPT, JSONDeserialize, JSONSerialize};
 
+#[cfg(feature = "json")]
+use super::common::BodyReader;
 use super::{
-    CollectionState,
-    common::{PendingFetch, execute_fetch},
+    CollectionState, FetchError, Subscription,
+    common::{Abort, PendingFetch, ResponseHeaders, execute_fetch},
     request::Request,
+    spawn_local, start_transfer,
     transferstate::{OperationState, TransferState},
 };
 
@@ -34,10 +49,64 @@ pub struct CollectionStore<E, MV = NoMac> {
     messages: Messages,
     paging: Mutable<Paging>,
     collection: MutableVec<E>,
+    sort: Option<Comparator<E>>,
+    dedupe: Option<Deduplicator<E>>,
+    sync: Option<Synchronizer<E>>,
+    logging: bool,
     pmv: PhantomData<MV>,
 }
 
+type Comparator<E> = Rc<dyn Fn(&E, &E) -> Ordering>;
+type Deduplicator<E> = Rc<dyn Fn(&mut Vec<E>)>;
+type Synchronizer<E> = Rc<dyn Fn(&MutableVec<E>, Vec<E>)>;
+
+// dedupe and sort applied to fetched collection, sync replaces it
+struct Arrangement<E> {
+    sort: Option<Comparator<E>>,
+    dedupe: Option<Deduplicator<E>>,
+    sync: Option<Synchronizer<E>>,
+}
+
+impl<E> Arrangement<E> {
+    fn is_empty(&self) -> bool {
+        self.sort.is_none() && self.dedupe.is_none()
+    }
+
+    fn apply(&self, values: &mut Vec<E>) {
+        if let Some(dedupe) = &self.dedupe {
+            dedupe(values);
+        }
+        if let Some(sort) = &self.sort {
+            values.sort_by(|a, b| sort(a, b));
+        }
+    }
+
+    fn replace(&self, collection: &MutableVec<E>, values: Vec<E>)
+    where
+        E: Clone,
+    {
+        match &self.sync {
+            Some(sync) => sync(collection, values),
+            None => collection.lock_mut().replace_cloned(values),
+        }
+    }
+}
+
 impl<E, MV> CollectionStore<E, MV> {
+    fn share(&self) -> Self {
+        Self {
+            transfer_state: self.transfer_state.clone(),
+            messages: self.messages.clone(),
+            paging: self.paging.clone(),
+            collection: self.collection.clone(),
+            sort: self.sort.clone(),
+            dedupe: self.dedupe.clone(),
+            sync: self.sync.clone(),
+            logging: self.logging,
+            pmv: PhantomData,
+        }
+    }
+
     #[inline]
     pub fn new() -> Self {
         Self::new_value(vec![])
@@ -49,10 +118,68 @@ impl<E, MV> CollectionStore<E, MV> {
             messages: Messages::new(),
             paging: Mutable::new(Paging::default()),
             collection: MutableVec::new_with_values(collection),
+            sort: None,
+            dedupe: None,
+            sync: None,
+            logging: true,
             pmv: PhantomData,
         }
     }
 
+    #[must_use]
+    pub fn with_sort<F>(mut self, compare: F) -> Self
+    where
+        F: Fn(&E, &E) -> Ordering + 'static,
+    {
+        self.sort = Some(Rc::new(compare));
+        self
+    }
+
+    #[must_use]
+    pub fn with_dedupe_key<K, F>(mut self, key_fn: F) -> Self
+    where
+        K: Eq + Hash,
+        F: Fn(&E) -> K + 'static,
+    {
+        self.dedupe = Some(Rc::new(move |values: &mut Vec<E>| {
+            retain_first_by_key(values, &key_fn)
+        }));
+        self
+    }
+
+    #[must_use]
+    pub fn with_sync_key<K, F>(mut self, key_fn: F) -> Self
+    where
+        E: Clone + PartialEq,
+        K: Eq + Hash,
+        F: Fn(&E) -> K + 'static,
+    {
+        self.sync = Some(Rc::new(move |collection: &MutableVec<E>, values| {
+            sync_by_key(collection, values, &key_fn)
+        }));
+        self
+    }
+
+    fn arrangement(&self) -> Arrangement<E> {
+        Arrangement {
+            sort: self.sort.clone(),
+            dedupe: self.dedupe.clone(),
+            sync: self.sync.clone(),
+        }
+    }
+
+    // `false` disables logging of all requests of the store
+    #[must_use]
+    pub fn with_logging(mut self, logging: bool) -> Self {
+        self.logging = logging;
+        self
+    }
+
+    fn apply_logging<'a>(&self, request: Request<'a>) -> Request<'a> {
+        let logging = self.logging && request.logging();
+        request.with_logging(logging)
+    }
+
     pub fn reset(&self) {
         self.transfer_state.set_neq(TransferState::Empty);
         self.messages.clear_all();
@@ -91,6 +218,17 @@ impl<E, MV> CollectionStore<E, MV> {
             .dedupe()
     }
 
+    // stays `true` during later reloads, observed from the moment of subscription
+    pub fn first_loaded_signal(&self) -> impl Signal<Item = bool> + use<E, MV> {
+        let mut loaded = false;
+        self.transfer_state
+            .signal_ref(move |state| {
+                loaded |= state.loaded();
+                loaded
+            })
+            .dedupe()
+    }
+
     #[inline]
     pub fn loaded_state(&self) -> OperationState {
         self.transfer_state.get().as_load()
@@ -148,6 +286,16 @@ impl<E, MV> CollectionStore<E, MV> {
             .dedupe()
     }
 
+    pub fn error_status(&self) -> Option<StatusCode> {
+        self.transfer_state.map(TransferState::error_status)
+    }
+
+    pub fn error_status_signal(&self) -> impl Signal<Item = Option<StatusCode>> + use<E, MV> {
+        self.transfer_state
+            .signal_ref(TransferState::error_status)
+            .dedupe()
+    }
+
     #[inline]
     pub fn pending(&self) -> bool {
         self.transfer_state.map(TransferState::pending)
@@ -232,6 +380,27 @@ impl<E, MV> CollectionStore<E, MV> {
         self.collection.inspect_vec_mut(f)
     }
 
+    // moves are used, so dependent signal vecs see `VecDiff::Move`
+    pub fn swap(&self, i: usize, j: usize) -> bool {
+        let mut collection = self.collection.lock_mut();
+        if i < collection.len() && j < collection.len() {
+            collection.swap(i, j);
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn move_item(&self, from: usize, to: usize) -> bool {
+        let mut collection = self.collection.lock_mut();
+        if from < collection.len() && to < collection.len() {
+            collection.move_from_to(from, to);
+            true
+        } else {
+            false
+        }
+    }
+
     pub fn find_map<F, U>(&self, f: F) -> Option<U>
     where
         F: Fn(&E) -> Option<U>,
@@ -253,10 +422,56 @@ where
         self.collection.signal_vec().is_empty().dedupe()
     }
 
+    pub fn len_signal(&self) -> impl Signal<Item = usize> + use<E, MV> {
+        self.collection.signal_vec().len().dedupe()
+    }
+
+    pub fn count_where_signal<F>(&self, predicate: F) -> impl Signal<Item = usize> + use<E, MV, F>
+    where
+        F: FnMut(&E) -> bool,
+    {
+        self.collection
+            .signal_vec()
+            .filter(predicate)
+            .len()
+            .dedupe()
+    }
+
     pub fn collection_state_signal(&self) -> impl Signal<Item = CollectionState> + use<E, MV> {
         collection_state_signal(self.loaded_state_signal(), self.empty_signal())
     }
 
+    pub fn track_dirty(&self) -> impl Signal<Item = bool> + use<E, MV>
+    where
+        E: Dirty,
+    {
+        self.collection
+            .signal_vec()
+            .to_signal_map(|items| items.iter().any(Dirty::is_dirty))
+            .dedupe()
+    }
+
+    pub fn dirty_count_signal(&self) -> impl Signal<Item = usize> + use<E, MV>
+    where
+        E: Dirty,
+    {
+        self.count_where_signal(Dirty::is_dirty)
+    }
+
+    pub fn clear_dirty(&self)
+    where
+        E: Dirty,
+    {
+        let mut lock = self.collection.lock_mut();
+        for index in 0..lock.len() {
+            if lock[index].is_dirty() {
+                let mut item = lock[index];
+                item.take_dirty();
+                lock.set(index, item);
+            }
+        }
+    }
+
     pub fn find<F>(&self, f: F) -> Option<E>
     where
         F: Fn(&E) -> bool,
@@ -306,9 +521,12 @@ where
     }
 
     pub fn set_externally_loaded(&self, values: Vec<E>) {
+        self.set_externally_loaded_with_status(values, StatusCode::Ok);
+    }
+
+    pub fn set_externally_loaded_with_status(&self, values: Vec<E>, status: StatusCode) {
         self.collection.lock_mut().replace(values);
-        self.transfer_state
-            .set_neq(TransferState::Loaded(StatusCode::Ok));
+        self.transfer_state.set_neq(TransferState::Loaded(status));
     }
 
     pub fn signal_map<F, U>(&self, f: F) -> impl Signal<Item = U> + use<E, MV, F, U>
@@ -367,12 +585,46 @@ where
     {
         self.collection.signal_vec().filter_map(f)
     }
+
+    pub fn sort_by<F>(&self, compare: F)
+    where
+        F: FnMut(&E, &E) -> Ordering,
+    {
+        let mut lock = self.collection.lock_mut();
+        let mut values = lock.to_vec();
+        values.sort_by(compare);
+        lock.replace(values);
+    }
+
+    pub fn signal_vec_sorted_by<F>(&self, compare: F) -> impl SignalVec<Item = E> + use<E, MV, F>
+    where
+        F: FnMut(&E, &E) -> Ordering,
+    {
+        self.collection.signal_vec().sort_by_cloned(compare)
+    }
 }
 
 impl<E, MV> CollectionStore<E, MV>
 where
     E: Clone,
 {
+    pub fn reset_to(&self, values: Vec<E>) {
+        self.transfer_state.set_neq(TransferState::Empty);
+        self.messages.clear_all();
+        self.paging.set(Paging::default());
+        self.collection.lock_mut().replace_cloned(values);
+    }
+
+    // unchanged items are left untouched in dependent signal vecs
+    pub fn sync_cloned<K, F>(&self, incoming: Vec<E>, key_fn: F)
+    where
+        E: PartialEq,
+        K: Eq + Hash,
+        F: Fn(&E) -> K,
+    {
+        sync_by_key(&self.collection, incoming, key_fn);
+    }
+
     #[inline]
     pub fn get_cloned(&self) -> Vec<E> {
         self.collection.lock_ref().to_vec()
@@ -382,12 +634,61 @@ where
         self.collection.signal_vec_cloned().is_empty().dedupe()
     }
 
+    pub fn len_signal_cloned(&self) -> impl Signal<Item = usize> + use<E, MV> {
+        self.collection.signal_vec_cloned().len().dedupe()
+    }
+
+    pub fn count_where_signal_cloned<F>(
+        &self,
+        predicate: F,
+    ) -> impl Signal<Item = usize> + use<E, MV, F>
+    where
+        F: FnMut(&E) -> bool,
+    {
+        self.collection
+            .signal_vec_cloned()
+            .filter(predicate)
+            .len()
+            .dedupe()
+    }
+
     pub fn collection_state_signal_cloned(
         &self,
     ) -> impl Signal<Item = CollectionState> + use<E, MV> {
         collection_state_signal(self.loaded_state_signal(), self.empty_signal_cloned())
     }
 
+    pub fn track_dirty_cloned(&self) -> impl Signal<Item = bool> + use<E, MV>
+    where
+        E: Dirty,
+    {
+        self.collection
+            .signal_vec_cloned()
+            .to_signal_map(|items| items.iter().any(Dirty::is_dirty))
+            .dedupe()
+    }
+
+    pub fn dirty_count_signal_cloned(&self) -> impl Signal<Item = usize> + use<E, MV>
+    where
+        E: Dirty,
+    {
+        self.count_where_signal_cloned(Dirty::is_dirty)
+    }
+
+    pub fn clear_dirty_cloned(&self)
+    where
+        E: Dirty,
+    {
+        let mut lock = self.collection.lock_mut();
+        for index in 0..lock.len() {
+            if lock[index].is_dirty() {
+                let mut item = lock[index].clone();
+                item.take_dirty();
+                lock.set_cloned(index, item);
+            }
+        }
+    }
+
     pub fn find_cloned<F>(&self, f: F) -> Option<E>
     where
         F: Fn(&E) -> bool,
@@ -437,9 +738,12 @@ where
     }
 
     pub fn set_externally_loaded_cloned(&self, values: Vec<E>) {
+        self.set_externally_loaded_with_status_cloned(values, StatusCode::Ok);
+    }
+
+    pub fn set_externally_loaded_with_status_cloned(&self, values: Vec<E>, status: StatusCode) {
         self.collection.lock_mut().replace_cloned(values);
-        self.transfer_state
-            .set_neq(TransferState::Loaded(StatusCode::Ok));
+        self.transfer_state.set_neq(TransferState::Loaded(status));
     }
 
     pub fn signal_map_cloned<F, U>(&self, f: F) -> impl Signal<Item = U> + use<E, MV, F, U>
@@ -501,6 +805,111 @@ where
     {
         self.collection.signal_vec_cloned().filter_map(f)
     }
+
+    pub fn sort_by_cloned<F>(&self, compare: F)
+    where
+        F: FnMut(&E, &E) -> Ordering,
+    {
+        arrange_collection(&self.collection, |values| values.sort_by(compare));
+    }
+
+    pub fn dedupe_by_key<K, F>(&self, key_fn: F)
+    where
+        K: Eq + Hash,
+        F: Fn(&E) -> K,
+    {
+        arrange_collection(&self.collection, |values| {
+            retain_first_by_key(values, key_fn)
+        });
+    }
+
+    pub fn signal_vec_sorted_by_cloned<F>(
+        &self,
+        compare: F,
+    ) -> impl SignalVec<Item = E> + use<E, MV, F>
+    where
+        F: FnMut(&E, &E) -> Ordering,
+    {
+        self.collection.signal_vec_cloned().sort_by_cloned(compare)
+    }
+}
+
+// completes a load started by `bind_url` once its callback is called, or dropped when refused
+struct BoundLoad(Mutable<bool>);
+
+impl Drop for BoundLoad {
+    fn drop(&mut self) {
+        self.0.set(true);
+    }
+}
+
+fn arrange_collection<E, F>(collection: &MutableVec<E>, arrange: F)
+where
+    E: Clone,
+    F: FnOnce(&mut Vec<E>),
+{
+    let mut lock = collection.lock_mut();
+    let mut values = lock.to_vec();
+    arrange(&mut values);
+    lock.replace_cloned(values);
+}
+
+// each entity is inserted after its equals, keeping the order of a stable sort
+#[cfg(feature = "json")]
+fn insert_sorted<E>(collection: &MutableVec<E>, entities: Vec<E>, sort: &Comparator<E>)
+where
+    E: Clone,
+{
+    let mut lock = collection.lock_mut();
+    for entity in entities {
+        let index = lock.partition_point(|existing| sort(existing, &entity) != Ordering::Greater);
+        lock.insert_cloned(index, entity);
+    }
+}
+
+fn sync_by_key<E, K, F>(collection: &MutableVec<E>, incoming: Vec<E>, key_fn: F)
+where
+    E: Clone + PartialEq,
+    K: Eq + Hash,
+    F: Fn(&E) -> K,
+{
+    let mut lock = collection.lock_mut();
+    let keys = incoming.iter().map(&key_fn).collect::<HashSet<_>>();
+    for index in (0..lock.len()).rev() {
+        if !keys.contains(&key_fn(&lock[index])) {
+            lock.remove(index);
+        }
+    }
+
+    let len = incoming.len();
+    for (index, value) in incoming.into_iter().enumerate() {
+        let key = key_fn(&value);
+        match lock[index..]
+            .iter()
+            .position(|current| key_fn(current) == key)
+        {
+            Some(offset) => {
+                if offset > 0 {
+                    lock.move_from_to(index + offset, index);
+                }
+                if lock[index] != value {
+                    lock.set_cloned(index, value);
+                }
+            }
+            None => lock.insert_cloned(index, value),
+        }
+    }
+    // leftovers of duplicate keys
+    lock.truncate(len);
+}
+
+fn retain_first_by_key<E, K, F>(values: &mut Vec<E>, key_fn: F)
+where
+    K: Eq + Hash,
+    F: Fn(&E) -> K,
+{
+    let mut seen = HashSet::new();
+    values.retain(|value| seen.insert(key_fn(value)));
 }
 
 impl<E, MV> CollectionStore<E, MV>
@@ -508,11 +917,21 @@ where
     E: Clone,
     MV: MacVerify,
 {
+    // on a cache hit the callback gets the status of the previous load
     pub fn load<C>(&self, request: Request<'_>, result_callback: C)
     where
         E: DeserializeOwned + 'static,
         C: FnOnce(StatusCode) + 'static,
     {
+        self.load_with_headers(request, move |status, _| result_callback(status));
+    }
+
+    pub fn load_with_headers<C>(&self, request: Request<'_>, result_callback: C)
+    where
+        E: DeserializeOwned + 'static,
+        C: FnOnce(StatusCode, ResponseHeaders) + 'static,
+    {
+        let request = self.apply_logging(request);
         if self.transfer_state.map(TransferState::loaded) {
             if request.logging() {
                 debug!("Request to load {} skipped, using cache", request.url());
@@ -524,8 +943,12 @@ where
                     );
                 }
             }
+            result_callback(
+                self.loaded_status().unwrap_or(StatusCode::Ok),
+                ResponseHeaders::default(),
+            );
         } else {
-            self.load_skip_cache(request, result_callback);
+            self.fetch_load(request, result_callback);
         }
     }
 
@@ -534,6 +957,80 @@ where
         E: DeserializeOwned + 'static,
         C: FnOnce(StatusCode) + 'static,
     {
+        self.fetch_load(request, move |status, _| result_callback(status));
+    }
+
+    // load in flight is aborted, its result is not passed to `result_callback`
+    pub fn bind_url<S, F, C>(
+        &self,
+        url_signal: S,
+        make_request: F,
+        result_callback: C,
+    ) -> Subscription
+    where
+        E: DeserializeOwned + 'static,
+        MV: 'static,
+        S: Signal<Item = SmolStr> + 'static,
+        F: Fn(&str) -> Request<'_> + 'static,
+        C: Fn(StatusCode) + 'static,
+    {
+        let subscription = Subscription::new();
+        let store = self.share();
+        let make_request = Rc::new(make_request);
+        let result_callback = Rc::new(result_callback);
+        let in_flight = Rc::new(RefCell::new(None::<(Abort, Mutable<bool>)>));
+
+        let urls = subscription.until_dropped(url_signal);
+        spawn_local(urls.for_each({
+            let active = subscription.active();
+            move |url| {
+                let active = active.clone();
+                let store = store.share();
+                let make_request = make_request.clone();
+                let result_callback = result_callback.clone();
+                let in_flight = in_flight.clone();
+                async move {
+                    let previous = in_flight.borrow_mut().take();
+                    if let Some((abort, completed)) = previous {
+                        abort.abort();
+                        completed.signal().wait_for(true).await;
+                    }
+                    if !active.get() {
+                        return;
+                    }
+
+                    let mut request = store.apply_logging(make_request(&url));
+                    let completed = Mutable::new(false);
+                    let load = BoundLoad(completed.clone());
+                    match Abort::new() {
+                        Ok(abort) => {
+                            request = request.with_abort(abort.clone());
+                            in_flight.replace(Some((abort, completed)));
+                        }
+                        Err(error) => {
+                            if request.logging() {
+                                warn!("Bound request {url} cannot be aborted: {error}");
+                            }
+                        }
+                    }
+                    store.load_skip_cache(request, move |status| {
+                        drop(load);
+                        if status != StatusCode::Aborted {
+                            result_callback(status);
+                        }
+                    });
+                }
+            }
+        }));
+        subscription
+    }
+
+    fn fetch_load<C>(&self, request: Request<'_>, result_callback: C)
+    where
+        E: DeserializeOwned + 'static,
+        C: FnOnce(StatusCode, ResponseHeaders) + 'static,
+    {
+        let request = self.apply_logging(request);
         if request.logging() {
             debug!("Request to load {}", request.url());
 
@@ -546,24 +1043,165 @@ where
         }
 
         let collection = self.collection.clone();
+        let arrangement = self.arrangement();
         fetch::<_, _, _, MV>(
             request.with_is_load(true),
             self.transfer_state.clone(),
             self.messages.clone(),
             self.paging.clone(),
-            move |new| {
-                collection.lock_mut().replace_cloned(new);
+            move |mut new| {
+                arrangement.apply(&mut new);
+                arrangement.replace(&collection, new);
             },
             result_callback,
         );
     }
 
+    // `false` if refused as another request is pending, the callback is not called then
+    pub fn load_more<C>(&self, request: Request<'_>, result_callback: C) -> bool
+    where
+        E: DeserializeOwned + 'static,
+        C: FnOnce(StatusCode) + 'static,
+    {
+        let request = self.apply_logging(request);
+        if self.pending() {
+            if request.logging() {
+                warn!(
+                    "Request to load more {} refused, another request is pending",
+                    request.url()
+                );
+            }
+            return false;
+        }
+
+        if request.logging() {
+            debug!("Request to load more {}", request.url());
+
+            if !request.method().is_load() {
+                warn!(
+                    "Load more request unexpectedly uses store verb {:?}",
+                    request.method().as_str()
+                );
+            }
+        }
+
+        let collection = self.collection.clone();
+        let arrangement = self.arrangement();
+        fetch::<_, _, _, MV>(
+            request.with_is_load(true),
+            self.transfer_state.clone(),
+            self.messages.clone(),
+            self.paging.clone(),
+            move |new| {
+                collection.extend_cloned(new);
+                if !arrangement.is_empty() {
+                    arrange_collection(&collection, |values| arrangement.apply(values));
+                }
+            },
+            move |status, _| result_callback(status),
+        );
+        true
+    }
+
+    // `false` like `load_more` when another request is pending
+    pub fn load_next_page<C>(&self, request: Request<'_>, result_callback: C) -> bool
+    where
+        E: DeserializeOwned + 'static,
+        C: FnOnce(StatusCode) + 'static,
+    {
+        let Some(next) = self.paging.lock_ref().next().map(SmolStr::from) else {
+            result_callback(self.loaded_status().unwrap_or(StatusCode::Ok));
+            return true;
+        };
+
+        self.load_more(request.with_query_param("cursor", next), result_callback)
+    }
+
+    // messages and paging are cleared
+    pub fn load_bare<C>(&self, request: Request<'_>, result_callback: C)
+    where
+        E: DeserializeOwned + 'static,
+        C: FnOnce(StatusCode) + 'static,
+    {
+        let request = self.apply_logging(request);
+        if request.logging() {
+            debug!("Request to load (bare) {}", request.url());
+        }
+
+        let collection = self.collection.clone();
+        let arrangement = self.arrangement();
+        fetch_as::<Vec<E>, _, _, _, MV>(
+            request.with_is_load(true),
+            self.transfer_state.clone(),
+            self.messages.clone(),
+            self.paging.clone(),
+            move |mut new| {
+                arrangement.apply(&mut new);
+                arrangement.replace(&collection, new);
+            },
+            move |status, _| result_callback(status),
+        );
+    }
+
+    // previous entities are restored when the response fails, signature is not verified
+    #[cfg(feature = "json")]
+    pub fn load_ndjson<C>(&self, request: Request<'_>, result_callback: C)
+    where
+        E: DeserializeOwned + 'static,
+        C: FnOnce(StatusCode) + 'static,
+    {
+        let request = self
+            .apply_logging(request)
+            .with_is_load(true)
+            .with_header(HEADER_ACCEPT, MediaType::Ndjson);
+        let logging = request.logging();
+        if logging {
+            debug!("Request to load (ndjson) {}", request.url());
+
+            if !request.method().is_load() {
+                warn!(
+                    "Load request unexpectedly uses store verb {:?}",
+                    request.method().as_str()
+                );
+            }
+        }
+
+        if !start_transfer(&request, &self.transfer_state) {
+            return;
+        }
+
+        let pending_fetch = match request.start() {
+            Ok(future) => future,
+            Err(error) => {
+                if logging {
+                    debug!("Request failed at init, error: {error}");
+                }
+                result_callback(StatusCode::BadRequest);
+                self.transfer_state.lock_mut().stop(StatusCode::FetchFailed);
+                return;
+            }
+        };
+
+        let transfer_state = self.transfer_state.clone();
+        let messages = self.messages.clone();
+        let collection = self.collection.clone();
+        let arrangement = self.arrangement();
+        spawn_local(async move {
+            let status =
+                execute_ndjson_fetch(pending_fetch, logging, &messages, &collection, arrangement)
+                    .await;
+            result_callback(status);
+            transfer_state.lock_mut().stop(status);
+        });
+    }
+
     pub fn load_merge<F, C>(&self, request: Request<'_>, merge_fn: F, result_callback: C)
     where
         E: DeserializeOwned + 'static,
         F: FnMut(Vec<E>) + 'static,
         C: FnOnce(StatusCode) + 'static,
     {
+        let request = self.apply_logging(request);
         if request.logging() {
             debug!("Request to load/merge {}", request.url());
 
@@ -580,7 +1218,7 @@ where
             self.messages.clone(),
             self.paging.clone(),
             merge_fn,
-            result_callback,
+            move |status, _| result_callback(status),
         );
     }
 
@@ -590,7 +1228,63 @@ where
         MS: MacSign,
         C: FnOnce(StatusCode) + 'static,
     {
-        let mut request = request.with_is_load(false);
+        self.store_with_headers::<MS, _>(request, move |status, _| result_callback(status));
+    }
+
+    // `optimistic` items are rolled back when the request fails
+    pub fn store_optimistic<MS, C>(
+        &self,
+        request: Request<'_>,
+        optimistic: Vec<E>,
+        result_callback: C,
+    ) where
+        E: Serialize + DeserializeOwned + 'static,
+        MS: MacSign,
+        C: FnOnce(StatusCode) + 'static,
+    {
+        let request = self.apply_logging(request).with_is_load(false);
+        if self.pending() {
+            if request.logging() {
+                warn!(
+                    "Optimistic request to update {} refused, another request is pending",
+                    request.url()
+                );
+            }
+            return;
+        }
+        if request.logging() {
+            debug!("Request to update {} optimistically", request.url());
+        }
+
+        // request body is prepared first, so nothing is left to roll back when it fails
+        let snapshot = self.get_cloned();
+        let mut content = snapshot.clone();
+        content.extend(optimistic.iter().cloned());
+        let request = match self.prepare_store::<MS>(request, &content) {
+            Ok(request) => request,
+            Err(status) => {
+                result_callback(status);
+                return;
+            }
+        };
+
+        self.collection.extend_cloned(optimistic);
+        let collection = self.collection.clone();
+        self.fetch_store(request, move |status, _| {
+            if status.is_failure() {
+                collection.lock_mut().replace_cloned(snapshot);
+            }
+            result_callback(status);
+        });
+    }
+
+    pub fn store_with_headers<MS, C>(&self, request: Request<'_>, result_callback: C)
+    where
+        E: Serialize + DeserializeOwned + 'static,
+        MS: MacSign,
+        C: FnOnce(StatusCode, ResponseHeaders) + 'static,
+    {
+        let request = self.apply_logging(request).with_is_load(false);
         if request.logging() {
             debug!("Request to update {}", request.url());
 
@@ -602,67 +1296,101 @@ where
             }
         }
 
-        {
-            // scope around vector and collection borrow
-            let collection = self.lock_ref();
-            if !collection.is_empty() {
-                let media_type = match request.media_type() {
-                    #[cfg(feature = "json")]
-                    Some(media_type @ MediaType::Json) => media_type,
-                    #[cfg(feature = "postcard")]
-                    Some(media_type @ MediaType::Postcard) => media_type,
-                    _ => {
-                        if request.logging() {
-                            warn!("Request failed as unsupported media type is requested");
-                        }
-                        self.messages.replace(Messages::from_service_error(
-                            "Request failed as unsupported media type is requested",
-                        ));
-                        self.transfer_state
-                            .lock_mut()
-                            .stop(StatusCode::UnsupportedMediaType);
-                        return;
-                    }
-                };
-
-                let content = collection.to_vec();
-                let bytes = match media_type {
-                    #[cfg(feature = "json")]
-                    MediaType::Json => content.to_json(),
-                    #[cfg(feature = "postcard")]
-                    MediaType::Postcard => content.to_postcard(),
-                    _ => {
-                        if request.logging() {
-                            error!("Unsupported media type requested, unexpected code flow");
-                        }
-                        return;
-                    }
-                };
-                let bytes = match bytes {
-                    Ok(bytes) => bytes,
-                    Err(error) => {
-                        if request.logging() {
-                            error!("Cannot serialize collection: {error}");
-                        }
-                        return;
-                    }
-                };
+        let request = match self.prepare_store::<MS>(request, &self.lock_ref()) {
+            Ok(request) => request,
+            Err(status) => {
+                result_callback(status, ResponseHeaders::default());
+                return;
+            }
+        };
+        self.fetch_store(request, result_callback);
+    }
 
-                if let Some(signature) = MS::sign(bytes.as_ref()) {
-                    request = request.with_header(HEADER_SIGNATURE, signature);
-                }
+    // serializes non-empty `content` into the request body
+    fn prepare_store<'a, MS>(
+        &self,
+        mut request: Request<'a>,
+        content: &[E],
+    ) -> Result<Request<'a>, StatusCode>
+    where
+        E: Serialize,
+        MS: MacSign,
+    {
+        if content.is_empty() {
+            return Ok(request);
+        }
 
-                request = request.with_body(bytes);
+        let media_type = match request.media_type() {
+            #[cfg(feature = "cbor")]
+            Some(media_type @ MediaType::Cbor) => media_type,
+            #[cfg(feature = "json")]
+            Some(media_type @ MediaType::Json) => media_type,
+            #[cfg(feature = "messagepack")]
+            Some(media_type @ MediaType::MsgPack) => media_type,
+            #[cfg(feature = "postcard")]
+            Some(media_type @ MediaType::Postcard) => media_type,
+            _ => {
+                if request.logging() {
+                    warn!("Request failed as unsupported media type is requested");
+                }
+                self.messages.replace(Messages::from_service_error(
+                    "Request failed as unsupported media type is requested",
+                ));
+                self.transfer_state
+                    .lock_mut()
+                    .stop(StatusCode::UnsupportedMediaType);
+                return Err(StatusCode::UnsupportedMediaType);
+            }
+        };
+
+        let bytes = match media_type {
+            #[cfg(feature = "cbor")]
+            MediaType::Cbor => content.to_cbor(),
+            #[cfg(feature = "json")]
+            MediaType::Json => content.to_json(),
+            #[cfg(feature = "messagepack")]
+            MediaType::MsgPack => content.to_msgpack(),
+            #[cfg(feature = "postcard")]
+            MediaType::Postcard => content.to_postcard(),
+            _ => {
+                if request.logging() {
+                    error!("Unsupported media type requested, unexpected code flow");
+                }
+                return Err(StatusCode::BadRequest);
             }
+        };
+        let bytes = match bytes {
+            Ok(bytes) => bytes,
+            Err(error) => {
+                if request.logging() {
+                    error!("Cannot serialize collection: {error}");
+                }
+                return Err(StatusCode::BadRequest);
+            }
+        };
+
+        if let Some(signature) = MS::sign(bytes.as_ref()) {
+            request = request.with_header(HEADER_SIGNATURE, signature);
         }
+        Ok(request.with_body(bytes))
+    }
 
+    fn fetch_store<C>(&self, request: Request<'_>, result_callback: C)
+    where
+        E: DeserializeOwned + 'static,
+        C: FnOnce(StatusCode, ResponseHeaders) + 'static,
+    {
         let collection = self.collection.clone();
+        let arrangement = self.arrangement();
         fetch::<_, _, _, MV>(
             request,
             self.transfer_state.clone(),
             self.messages.clone(),
             self.paging.clone(),
-            move |new| collection.lock_mut().replace_cloned(new),
+            move |mut new| {
+                arrangement.apply(&mut new);
+                arrangement.replace(&collection, new);
+            },
             result_callback,
         );
     }
@@ -678,27 +1406,51 @@ fn fetch<E, F, C, MV>(
 ) where
     E: Clone + DeserializeOwned + 'static,
     F: FnMut(Vec<E>) + 'static,
-    C: FnOnce(StatusCode) + 'static,
+    C: FnOnce(StatusCode, ResponseHeaders) + 'static,
+    MV: MacVerify,
+{
+    fetch_as::<CollectionResponse<E>, _, _, _, MV>(
+        request,
+        transfer_state,
+        messages,
+        paging,
+        store_fn,
+        result_callback,
+    );
+}
+
+// response is decoded as `R` and converted to `CollectionResponse`
+fn fetch_as<R, E, F, C, MV>(
+    request: Request<'_>,
+    transfer_state: Mutable<TransferState>,
+    messages: Messages,
+    paging: Mutable<Paging>,
+    store_fn: F,
+    result_callback: C,
+) where
+    R: DeserializeOwned + Into<CollectionResponse<E>> + 'static,
+    E: Clone + DeserializeOwned + 'static,
+    F: FnMut(Vec<E>) + 'static,
+    C: FnOnce(StatusCode, ResponseHeaders) + 'static,
     MV: MacVerify,
 {
     let logging = request.logging();
 
+    if !start_transfer(&request, &transfer_state) {
+        return;
+    }
+
     let pending_fetch = match request.start() {
         Ok(future) => future,
         Err(error) => {
             if logging {
                 debug!("Request failed at init, error: {error}");
             }
-            result_callback(StatusCode::BadRequest);
+            result_callback(StatusCode::BadRequest, ResponseHeaders::default());
             transfer_state.lock_mut().stop(StatusCode::FetchFailed);
             return;
         }
     };
-    if request.is_load() {
-        transfer_state.lock_mut().start_load();
-    } else {
-        transfer_state.lock_mut().start_store();
-    }
 
     let context = CollectionFetchContext::<F> {
         logging,
@@ -708,13 +1460,14 @@ fn fetch<E, F, C, MV>(
     };
 
     spawn_local(async move {
-        let status = execute_collection_fetch::<_, _, MV>(pending_fetch, context).await;
-        result_callback(status);
+        let (status, headers) =
+            execute_collection_fetch::<R, _, _, MV>(pending_fetch, context).await;
+        result_callback(status, headers);
         transfer_state.lock_mut().stop(status);
     });
 }
 
-async fn execute_collection_fetch<E, F, MV>(
+async fn execute_collection_fetch<R, E, F, MV>(
     pending_fetch: PendingFetch,
     CollectionFetchContext {
         logging,
@@ -722,14 +1475,17 @@ async fn execute_collection_fetch<E, F, MV>(
         paging,
         mut store_fn,
     }: CollectionFetchContext<F>,
-) -> StatusCode
+) -> (StatusCode, ResponseHeaders)
 where
+    R: DeserializeOwned + Into<CollectionResponse<E>>,
     E: Clone + DeserializeOwned,
     F: FnMut(Vec<E>) + 'static,
     MV: MacVerify,
 {
-    let mut result = execute_fetch::<CollectionResponse<E>, MV>(pending_fetch).await;
-    match (result.status(), result.take_response()) {
+    let mut result = execute_fetch::<R, MV>(pending_fetch)
+        .await
+        .map_response(Into::<CollectionResponse<E>>::into);
+    let status = match (result.status(), result.take_response()) {
         (status @ StatusCode::FetchTimeout, _) => {
             if logging {
                 // TODO: should this warning go also to Messages???
@@ -740,11 +1496,12 @@ where
             }
             status
         }
-        (status @ StatusCode::FetchFailed, _) => {
+        (status @ (StatusCode::FetchFailed | StatusCode::Aborted), _) => {
             if logging {
                 // TODO: should this warning go also to Messages???
                 debug!(
-                    "Request failed in execution, error: {}",
+                    "Request failed in execution ({}), error: {}",
+                    result.error().map(FetchError::name).unwrap_or("?unknown"),
                     result.hint().unwrap_or("?unknown")
                 );
             }
@@ -765,6 +1522,7 @@ where
             let (response_entities, response_messages, response_paging) = response.take();
             messages.replace(response_messages);
             if status.is_success()
+                && status != StatusCode::NotModified
                 && let Some(response_entities) = response_entities
             {
                 if logging {
@@ -775,7 +1533,127 @@ where
             *paging.lock_mut() = response_paging;
             status
         }
+    };
+    (status, result.take_headers())
+}
+
+#[cfg(feature = "json")]
+async fn execute_ndjson_fetch<E>(
+    pending_fetch: PendingFetch,
+    logging: bool,
+    messages: &Messages,
+    collection: &MutableVec<E>,
+    arrangement: Arrangement<E>,
+) -> StatusCode
+where
+    E: Clone + DeserializeOwned,
+{
+    let mut fetched = pending_fetch.wait_completion().await;
+    let status = fetched.status();
+    let response = match fetched.take_response() {
+        Some(response) if status.is_success() && status != StatusCode::NotModified => response,
+        _ => {
+            if logging && status.is_local() {
+                debug!(
+                    "Request failed in execution, error: {}",
+                    fetched.hint().unwrap_or("?unknown")
+                );
+            }
+            return status;
+        }
+    };
+
+    let headers = fetched.take_headers();
+    let content_type = headers.get("Content-Type").unwrap_or_default();
+    if MediaType::from(content_type) != MediaType::Ndjson {
+        if logging {
+            warn!("Response is not ndjson, content type: {content_type}");
+        }
+        messages.replace(Messages::from_service_error(
+            "Request failed as response cannot be decoded",
+        ));
+        return StatusCode::UnsupportedMediaType;
+    }
+
+    messages.clear_all();
+    // restored when the response fails midway
+    let previous = collection.lock_ref().to_vec();
+    collection.lock_mut().clear();
+
+    let mut reader = BodyReader::new(response);
+    let mut buffer = Vec::new();
+    let result = loop {
+        let chunk = match reader.next_chunk().await {
+            Ok(chunk) => chunk,
+            Err(_) => {
+                if logging {
+                    debug!("Request failed while reading response body");
+                }
+                break Err(StatusCode::FetchFailed);
+            }
+        };
+        let done = chunk.is_none();
+        if let Some(chunk) = chunk {
+            buffer.extend(chunk);
+        }
+
+        // the last line may be incomplete until the stream is done
+        let end = if done {
+            buffer.len()
+        } else {
+            buffer
+                .iter()
+                .rposition(|byte| *byte == b'\n')
+                .map_or(0, |position| position + 1)
+        };
+        let entities = match decode_ndjson_lines::<E>(&buffer[..end]) {
+            Ok(entities) => entities,
+            Err(error) => {
+                if logging {
+                    warn!("Response decoding failed, error: {error}");
+                }
+                messages.replace(Messages::from_service_error(
+                    "Request failed as response cannot be decoded",
+                ));
+                break Err(StatusCode::DecodeFailed);
+            }
+        };
+        buffer.drain(..end);
+
+        match &arrangement.sort {
+            Some(sort) => insert_sorted(collection, entities, sort),
+            None => collection.extend_cloned(entities),
+        }
+        if done {
+            break Ok(());
+        }
+    };
+
+    if let Err(status) = result {
+        collection.lock_mut().replace_cloned(previous);
+        return status;
+    }
+    if let Some(dedupe) = &arrangement.dedupe {
+        arrange_collection(collection, |values| dedupe(values));
     }
+
+    if logging {
+        trace!("Request successfully fetched collection.");
+    }
+    status
+}
+
+#[cfg(feature = "json")]
+fn decode_ndjson_lines<E>(lines: &[u8]) -> Result<Vec<E>, SmolStr>
+where
+    E: DeserializeOwned,
+{
+    lines
+        .split(|byte| *byte == b'\n')
+        .map(<[u8]>::trim_ascii)
+        .filter(|line| !line.is_empty())
+        .map(E::try_from_json)
+        .collect()
 }
 
 impl<E, MV> Default for CollectionStore<E, MV> {
@@ -809,3 +1687,239 @@ where
     }
     .dedupe()
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        cell::Cell,
+        pin::Pin,
+        task::{Context, Poll, Waker},
+    };
+
+    use futures_signals::signal_vec::VecDiff;
+
+    use super::*;
+
+    fn changes<S: SignalVec + Unpin>(signal_vec: &mut S) -> Vec<VecDiff<S::Item>> {
+        let mut cx = Context::from_waker(Waker::noop());
+        let mut changes = vec![];
+        while let Poll::Ready(Some(change)) = Pin::new(&mut *signal_vec).poll_vec_change(&mut cx) {
+            changes.push(change);
+        }
+        changes
+    }
+
+    #[test]
+    fn first_loaded_latches() {
+        let store = CollectionStore::<u8>::new();
+        let mut signal = store.first_loaded_signal();
+        let mut poll =
+            || Pin::new(&mut signal).poll_change(&mut Context::from_waker(Waker::noop()));
+        assert_eq!(Poll::Ready(Some(false)), poll());
+
+        store.set_transfer_state(TransferState::Loaded(StatusCode::Ok));
+        assert_eq!(Poll::Ready(Some(true)), poll());
+
+        store.set_transfer_state(TransferState::PendingLoad);
+        assert_eq!(Poll::Pending, poll());
+    }
+
+    #[test]
+    fn externally_loaded_keeps_status() {
+        let store = CollectionStore::<u8>::new();
+        store.set_externally_loaded_with_status(vec![1], StatusCode::NotModified);
+        assert!(store.loaded());
+        assert_eq!(Some(StatusCode::NotModified), store.loaded_status());
+        assert_eq!(vec![1], store.get());
+    }
+
+    #[test]
+    fn count_where_follows_items() {
+        let store = CollectionStore::<u8>::new_value(vec![1, 2, 3]);
+        let mut signal = store.count_where_signal(|item| item % 2 == 1);
+        let mut poll =
+            || Pin::new(&mut signal).poll_change(&mut Context::from_waker(Waker::noop()));
+        assert_eq!(Poll::Ready(Some(2)), poll());
+
+        store.lock_mut().push(4);
+        assert_eq!(Poll::Pending, poll());
+
+        store.lock_mut().push(5);
+        assert_eq!(Poll::Ready(Some(3)), poll());
+    }
+
+    #[derive(Clone)]
+    struct Row {
+        id: u8,
+        dirty: bool,
+    }
+
+    impl Dirty for Row {
+        fn is_dirty(&self) -> bool {
+            self.dirty
+        }
+
+        fn take_dirty(&mut self) -> bool {
+            std::mem::take(&mut self.dirty)
+        }
+
+        fn with_dirty(mut self) -> Self {
+            self.dirty = true;
+            self
+        }
+
+        fn mark_as_dirty(&mut self) {
+            self.dirty = true;
+        }
+    }
+
+    #[test]
+    fn track_dirty_follows_items() {
+        let row = |id| Row { id, dirty: false };
+        let store = CollectionStore::<Row>::new_value(vec![row(1), row(2)]);
+        let mut signal = store.track_dirty_cloned();
+        let mut poll =
+            || Pin::new(&mut signal).poll_change(&mut Context::from_waker(Waker::noop()));
+        assert_eq!(Poll::Ready(Some(false)), poll());
+
+        store.find_inspect_mut_cloned(
+            |row| row.id == 2,
+            |row| {
+                row.mark_as_dirty();
+                true
+            },
+        );
+        assert_eq!(Poll::Ready(Some(true)), poll());
+
+        store.lock_mut().push_cloned(row(3));
+        assert_eq!(Poll::Pending, poll());
+
+        store.remove_cloned(|row| row.id == 2);
+        assert_eq!(Poll::Ready(Some(false)), poll());
+    }
+
+    #[test]
+    fn dirty_count_cleared() {
+        let row = |id, dirty| Row { id, dirty };
+        let store =
+            CollectionStore::<Row>::new_value(vec![row(1, true), row(2, false), row(3, true)]);
+        let mut signal = store.dirty_count_signal_cloned();
+        let mut poll =
+            || Pin::new(&mut signal).poll_change(&mut Context::from_waker(Waker::noop()));
+        assert_eq!(Poll::Ready(Some(2)), poll());
+
+        store.clear_dirty_cloned();
+        assert_eq!(Poll::Ready(Some(0)), poll());
+        assert!(store.get_cloned().iter().all(|row| !row.dirty));
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn optimistic_rolled_back_when_not_serialized() {
+        use serde::{Deserialize, Serializer, ser::Error};
+
+        #[derive(Clone, Debug, PartialEq, Deserialize)]
+        struct Item(u8);
+
+        impl Serialize for Item {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                match self.0 {
+                    0 => Err(S::Error::custom("zero cannot be stored")),
+                    value => serializer.serialize_u8(value),
+                }
+            }
+        }
+
+        let store = CollectionStore::<Item>::new_value(vec![Item(1)]);
+        let status = Rc::new(Cell::new(None));
+        store.store_optimistic::<NoMac, _>(
+            Request::new("items").with_media_type(MediaType::Json),
+            vec![Item(2), Item(0)],
+            {
+                let status = status.clone();
+                move |result| status.set(Some(result))
+            },
+        );
+        assert_eq!(Some(StatusCode::BadRequest), status.get());
+        assert_eq!(vec![Item(1)], store.get_cloned());
+        assert!(!store.pending());
+    }
+
+    #[test]
+    fn load_more_refused_while_pending() {
+        let store = CollectionStore::<u8>::new();
+        store.set_transfer_state(TransferState::PendingLoad);
+        let called = Rc::new(Cell::new(false));
+        let started = store.load_more(Request::new("items"), {
+            let called = called.clone();
+            move |_| called.set(true)
+        });
+        assert!(!started);
+        assert!(!called.get());
+    }
+
+    #[test]
+    fn sync_keeps_unchanged_items() {
+        let store =
+            CollectionStore::<(char, u8)>::new_value(vec![('a', 1), ('b', 1), ('c', 1), ('d', 1)]);
+        let mut signal_vec = store.signal_vec_cloned();
+        changes(&mut signal_vec);
+
+        store.sync_cloned(vec![('a', 1), ('c', 2), ('e', 1), ('d', 1)], |item| item.0);
+        assert_eq!(
+            vec![
+                VecDiff::RemoveAt { index: 1 },
+                VecDiff::UpdateAt {
+                    index: 1,
+                    value: ('c', 2)
+                },
+                VecDiff::InsertAt {
+                    index: 2,
+                    value: ('e', 1)
+                },
+            ],
+            changes(&mut signal_vec)
+        );
+        assert_eq!(
+            vec![('a', 1), ('c', 2), ('e', 1), ('d', 1)],
+            store.get_cloned()
+        );
+    }
+
+    #[test]
+    fn sync_moves_reordered_items() {
+        let store = CollectionStore::<(char, u8)>::new_value(vec![('a', 1), ('b', 1), ('c', 1)]);
+        let mut signal_vec = store.signal_vec_cloned();
+        changes(&mut signal_vec);
+
+        store.sync_cloned(vec![('c', 1), ('a', 1), ('b', 1)], |item| item.0);
+        assert_eq!(
+            vec![VecDiff::Move {
+                old_index: 2,
+                new_index: 0
+            }],
+            changes(&mut signal_vec)
+        );
+        assert_eq!(vec![('c', 1), ('a', 1), ('b', 1)], store.get_cloned());
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn insert_sorted_keeps_stable_order() {
+        let collection = MutableVec::new_with_values(vec![('a', 1), ('c', 1)]);
+        let sort: Comparator<(char, u8)> = Rc::new(|a, b| a.0.cmp(&b.0));
+
+        insert_sorted(&collection, vec![('c', 2), ('b', 1), ('a', 2)], &sort);
+        assert_eq!(
+            vec![('a', 1), ('a', 2), ('b', 1), ('c', 1), ('c', 2)],
+            collection.lock_ref().to_vec()
+        );
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn ndjson_lines_decoded() {
+        assert_eq!(Ok(vec![1u8, 2]), decode_ndjson_lines::<u8>(b"1\r\n\n 2 \n"));
+        assert!(decode_ndjson_lines::<u8>(b"1\nx\n").is_err());
+    }
+}
@@ -1,3 +1,8 @@
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
 use futures_signals::{map_ref, signal::Signal};
 
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
@@ -79,3 +84,109 @@ where
         }
     )
 }
+
+// signals of different stores can be combined after `boxed_local`
+pub fn any_pending<I, S>(signals: I) -> AnyPending<S>
+where
+    I: IntoIterator<Item = S>,
+    S: Signal<Item = bool>,
+{
+    AnyPending {
+        signals: signals
+            .into_iter()
+            .map(|signal| (Some(Box::pin(signal)), None))
+            .collect(),
+        last: None,
+    }
+}
+
+type PendingSlot<S> = (Option<Pin<Box<S>>>, Option<bool>);
+
+#[must_use = "Signals do nothing unless polled"]
+pub struct AnyPending<S> {
+    signals: Vec<PendingSlot<S>>,
+    last: Option<bool>,
+}
+
+impl<S> Signal for AnyPending<S>
+where
+    S: Signal<Item = bool>,
+{
+    type Item = bool;
+
+    fn poll_change(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        let mut done = true;
+        for (signal, value) in &mut this.signals {
+            if let Some(inner) = signal {
+                match inner.as_mut().poll_change(cx) {
+                    Poll::Ready(Some(pending)) => {
+                        *value = Some(pending);
+                        done = false;
+                    }
+                    Poll::Ready(None) => *signal = None,
+                    Poll::Pending => done = false,
+                }
+            }
+        }
+
+        if this.signals.iter().all(|(_, value)| value.is_some()) {
+            let pending = this.signals.iter().any(|(_, value)| *value == Some(true));
+            if this.last != Some(pending) {
+                this.last = Some(pending);
+                return Poll::Ready(Some(pending));
+            }
+        }
+
+        if done {
+            Poll::Ready(None)
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::task::Waker;
+
+    use futures_signals::signal::{Mutable, SignalExt};
+
+    use super::*;
+
+    fn poll<S: Signal<Item = bool> + Unpin>(signal: &mut S) -> Poll<Option<bool>> {
+        Pin::new(signal).poll_change(&mut Context::from_waker(Waker::noop()))
+    }
+
+    #[test]
+    fn any_pending_combines() {
+        let first = Mutable::new(false);
+        let second = Mutable::new(false);
+        let mut signal = any_pending([
+            first.signal().boxed_local(),
+            second.signal_ref(|pending| *pending).boxed_local(),
+        ]);
+
+        assert_eq!(poll(&mut signal), Poll::Ready(Some(false)));
+        assert_eq!(poll(&mut signal), Poll::Pending);
+
+        second.set(true);
+        assert_eq!(poll(&mut signal), Poll::Ready(Some(true)));
+
+        first.set(true);
+        assert_eq!(poll(&mut signal), Poll::Pending);
+
+        second.set(false);
+        assert_eq!(poll(&mut signal), Poll::Pending);
+
+        first.set(false);
+        assert_eq!(poll(&mut signal), Poll::Ready(Some(false)));
+    }
+
+    #[test]
+    fn any_pending_empty() {
+        let mut signal = any_pending(Vec::<futures_signals::signal::Always<bool>>::new());
+        assert_eq!(poll(&mut signal), Poll::Ready(Some(false)));
+        assert_eq!(poll(&mut signal), Poll::Ready(None));
+    }
+}
@@ -1,46 +1,244 @@
-use std::time::Duration;
+use std::{cell::Cell, collections::BTreeMap, rc::Rc, time::Duration};
 
-use artwrap::TimeoutFutureExt;
 use base64::{Engine, engine::general_purpose};
-use js_sys::{JsString, Uint8Array};
-use smol_str::{SmolStr, ToSmolStr, format_smolstr};
-use wasm_bindgen::{JsCast, JsValue};
+use futures_signals::signal::Mutable;
+use smol_str::{SmolStr, ToSmolStr};
+#[cfg(feature = "browser")]
+use std::any::Any;
+
+#[cfg(feature = "browser")]
+use artwrap::TimeoutFutureExt;
+#[cfg(feature = "browser")]
+use js_sys::{Array, JsString, Uint8Array};
+#[cfg(feature = "browser")]
+use wasm_bindgen::{JsCast, JsValue, closure::Closure};
+#[cfg(feature = "browser")]
 use wasm_bindgen_futures::JsFuture;
-use web_sys::{AbortController, AbortSignal, Response, ResponseType};
+#[cfg(feature = "browser")]
+use web_sys::{
+    AbortController, AbortSignal, Headers, ReadableStreamDefaultReader, ReadableStreamReadResult,
+    Response, ResponseType,
+};
 
-use crate::{HEADER_SIGNATURE, MacVerify, MediaType, StatusCode, uformat_smolstr};
+use crate::{
+    HEADER_ALLOW, HEADER_CONTENT_ENCODING, HEADER_CONTENT_LENGTH, HEADER_RETRY_AFTER,
+    HEADER_SIGNATURE, MacVerify, MediaType, StatusCode, uformat_smolstr,
+};
+
+#[cfg(feature = "cbor")]
+use crate::CborDeserialize;
 
 #[cfg(feature = "json")]
 use crate::JSONDeserialize;
 
+#[cfg(feature = "messagepack")]
+use crate::MsgPackDeserialize;
+
 #[cfg(feature = "postcard")]
 use crate::PostcardDeserialize;
 
+#[cfg(feature = "browser")]
 use super::js_error;
+#[cfg(not(feature = "browser"))]
+pub use super::native::Abort;
+#[cfg(not(feature = "browser"))]
+pub(crate) use super::native::{BodyReader, PendingFetch, Response};
+#[cfg(not(feature = "browser"))]
+use super::native::{now_seconds, response_bytes};
+use super::{FetchError, request::Method, transferstate::TransferState};
+
+// bytes of text content included in the hint when it cannot be deserialized
+const DECODE_SNIPPET_LEN: usize = 80;
+
 pub fn none(_: StatusCode) {}
 
-#[cfg(all(feature = "json", feature = "postcard"))]
-pub trait FetchDeserializable: JSONDeserialize + PostcardDeserialize {}
-#[cfg(all(feature = "json", feature = "postcard"))]
-impl<F> FetchDeserializable for F where F: JSONDeserialize + PostcardDeserialize {}
+pub trait FetchDeserializable: FetchCbor + FetchJson + FetchMsgPack + FetchPostcard {}
+impl<F> FetchDeserializable for F where F: FetchCbor + FetchJson + FetchMsgPack + FetchPostcard {}
+
+#[cfg(feature = "cbor")]
+pub trait FetchCbor: CborDeserialize {}
+#[cfg(feature = "cbor")]
+impl<F> FetchCbor for F where F: CborDeserialize {}
+#[cfg(not(feature = "cbor"))]
+pub trait FetchCbor {}
+#[cfg(not(feature = "cbor"))]
+impl<F> FetchCbor for F {}
+
+#[cfg(feature = "json")]
+pub trait FetchJson: JSONDeserialize {}
+#[cfg(feature = "json")]
+impl<F> FetchJson for F where F: JSONDeserialize {}
+#[cfg(not(feature = "json"))]
+pub trait FetchJson {}
+#[cfg(not(feature = "json"))]
+impl<F> FetchJson for F {}
+
+#[cfg(feature = "messagepack")]
+pub trait FetchMsgPack: MsgPackDeserialize {}
+#[cfg(feature = "messagepack")]
+impl<F> FetchMsgPack for F where F: MsgPackDeserialize {}
+#[cfg(not(feature = "messagepack"))]
+pub trait FetchMsgPack {}
+#[cfg(not(feature = "messagepack"))]
+impl<F> FetchMsgPack for F {}
+
+#[cfg(feature = "postcard")]
+pub trait FetchPostcard: PostcardDeserialize {}
+#[cfg(feature = "postcard")]
+impl<F> FetchPostcard for F where F: PostcardDeserialize {}
+#[cfg(not(feature = "postcard"))]
+pub trait FetchPostcard {}
+#[cfg(not(feature = "postcard"))]
+impl<F> FetchPostcard for F {}
+
+#[derive(Clone, Debug, Default)]
+pub struct ResponseHeaders {
+    headers: BTreeMap<SmolStr, SmolStr>,
+    final_url: Option<SmolStr>,
+}
+
+impl ResponseHeaders {
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.headers.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.headers
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+    }
+
+    pub fn final_url(&self) -> Option<&str> {
+        self.final_url.as_deref()
+    }
+
+    // given either in seconds or as HTTP-date
+    pub fn retry_after(&self) -> Option<Duration> {
+        self.get(HEADER_RETRY_AFTER)
+            .and_then(|value| retry_after_delay(value, now_seconds()))
+    }
+
+    // unknown methods are skipped
+    pub fn allow(&self) -> Vec<Method> {
+        self.get(HEADER_ALLOW)
+            .map(allowed_methods)
+            .unwrap_or_default()
+    }
+
+    fn with_final_url(mut self, final_url: Option<SmolStr>) -> Self {
+        self.final_url = final_url;
+        self
+    }
+}
+
+impl<N, V> FromIterator<(N, V)> for ResponseHeaders
+where
+    N: AsRef<str>,
+    V: Into<SmolStr>,
+{
+    fn from_iter<I: IntoIterator<Item = (N, V)>>(headers: I) -> Self {
+        Self {
+            headers: headers
+                .into_iter()
+                .map(|(name, value)| (name.as_ref().to_ascii_lowercase().into(), value.into()))
+                .collect(),
+            final_url: None,
+        }
+    }
+}
+
+#[cfg(feature = "browser")]
+fn now_seconds() -> u64 {
+    (js_sys::Date::now() / 1000.0) as u64
+}
 
-#[cfg(all(feature = "json", not(feature = "postcard")))]
-pub trait FetchDeserializable: JSONDeserialize {}
-#[cfg(all(feature = "json", not(feature = "postcard")))]
-impl<F> FetchDeserializable for F where F: JSONDeserialize {}
+fn allowed_methods(value: &str) -> Vec<Method> {
+    value
+        .split(',')
+        .filter_map(|verb| verb.trim().parse().ok())
+        .collect()
+}
 
-#[cfg(all(not(feature = "json"), feature = "postcard"))]
-pub trait FetchDeserializable: PostcardDeserialize {}
-#[cfg(all(not(feature = "json"), feature = "postcard"))]
-impl<F> FetchDeserializable for F where F: PostcardDeserialize {}
+// `now` and the result are in seconds since the Unix epoch, date in the past means no delay
+fn retry_after_delay(value: &str, now: u64) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    http_date_seconds(value).map(|date| Duration::from_secs(date.saturating_sub(now)))
+}
 
-#[cfg(all(not(feature = "json"), not(feature = "postcard")))]
-pub trait FetchDeserializable {}
+// IMF-fixdate only, e.g. `Sun, 06 Nov 1994 08:49:37 GMT`
+fn http_date_seconds(value: &str) -> Option<u64> {
+    const MONTHS: [&str; 12] = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ];
 
+    let [_, day, month, year, time, "GMT"] = value.split_ascii_whitespace().collect::<Vec<_>>()[..]
+    else {
+        return None;
+    };
+    let day = day
+        .parse::<u64>()
+        .ok()
+        .filter(|day| (1..=31).contains(day))?;
+    let month = MONTHS.iter().position(|name| *name == month)? as u64 + 1;
+    let year = year.parse::<u64>().ok().filter(|year| *year >= 1970)?;
+    let mut time = time.split(':').map(|part| part.parse::<u64>().ok());
+    let (Some(Some(hours)), Some(Some(minutes)), Some(Some(seconds)), None) =
+        (time.next(), time.next(), time.next(), time.next())
+    else {
+        return None;
+    };
+
+    // days from civil, shifted to start the year in March
+    let (year, month) = if month <= 2 {
+        (year - 1, month + 9)
+    } else {
+        (year, month - 3)
+    };
+    let era = year / 400;
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * month + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146097 + day_of_era - 719468;
+    Some(days * 86400 + hours * 3600 + minutes * 60 + seconds)
+}
+
+#[cfg(feature = "browser")]
+impl From<&Headers> for ResponseHeaders {
+    fn from(headers: &Headers) -> Self {
+        let Ok(Some(entries)) = js_sys::try_iter(headers) else {
+            return Self::default();
+        };
+        entries
+            .flatten()
+            .filter_map(|entry| {
+                let entry = Array::from(&entry);
+                entry.get(0).as_string().zip(entry.get(1).as_string())
+            })
+            .collect()
+    }
+}
+
+#[cfg(feature = "browser")]
+#[derive(Clone)]
 pub struct Abort {
     controller: AbortController,
 }
 
+#[cfg(feature = "browser")]
 impl Abort {
     pub fn new() -> Result<Self, SmolStr> {
         Ok(Self {
@@ -55,16 +253,159 @@ impl Abort {
     pub fn abort(&self) {
         self.controller.abort()
     }
+
+    pub fn is_aborted(&self) -> bool {
+        self.controller.signal().aborted()
+    }
+}
+
+// cancel or drop aborts the fetch, resets transfer state and suppresses the callback
+#[must_use = "dropping FetchHandle cancels the fetch, use `detach` to keep it running"]
+pub struct FetchHandle {
+    pending: Option<PendingHandle>,
+}
+
+struct PendingHandle {
+    abort: Abort,
+    progress: FetchProgress,
+    transfer: StartedTransfer,
+}
+
+// transfer of a store started by a fetch, the store may start a newer one meanwhile
+struct StartedTransfer {
+    transfer_state: Mutable<TransferState>,
+    transfer_generation: Mutable<u64>,
+    generation: u64,
+}
+
+impl StartedTransfer {
+    fn new(transfer_state: Mutable<TransferState>, transfer_generation: Mutable<u64>) -> Self {
+        let generation = next_transfer_generation(&transfer_generation);
+        Self {
+            transfer_state,
+            transfer_generation,
+            generation,
+        }
+    }
+
+    // the state of a newer transfer is kept
+    fn reset(&self) {
+        if self.transfer_generation.get() == self.generation {
+            self.transfer_state.set_neq(TransferState::Empty);
+        }
+    }
 }
 
+pub(crate) fn next_transfer_generation(transfer_generation: &Mutable<u64>) -> u64 {
+    let mut generation = transfer_generation.lock_mut();
+    *generation = generation.wrapping_add(1);
+    *generation
+}
+
+impl FetchHandle {
+    pub(crate) fn completed() -> Self {
+        Self { pending: None }
+    }
+
+    pub(crate) fn new(
+        abort: Abort,
+        transfer_state: Mutable<TransferState>,
+        transfer_generation: Mutable<u64>,
+    ) -> Self {
+        Self {
+            pending: Some(PendingHandle {
+                abort,
+                progress: FetchProgress::default(),
+                transfer: StartedTransfer::new(transfer_state, transfer_generation),
+            }),
+        }
+    }
+
+    pub(crate) fn progress(&self) -> FetchProgress {
+        self.pending
+            .as_ref()
+            .map(|pending| pending.progress.clone())
+            .unwrap_or_default()
+    }
+
+    pub fn detach(mut self) {
+        self.pending = None;
+    }
+
+    pub fn is_pending(&self) -> bool {
+        self.pending
+            .as_ref()
+            .is_some_and(|pending| pending.progress.is_running())
+    }
+
+    pub fn cancel(&self) {
+        if let Some(pending) = &self.pending
+            && pending.progress.cancel()
+        {
+            pending.abort.abort();
+            pending.transfer.reset();
+        }
+    }
+}
+
+impl Drop for FetchHandle {
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum FetchPhase {
+    Running,
+    Completed,
+    Cancelled,
+}
+
+#[derive(Clone)]
+pub(crate) struct FetchProgress(Rc<Cell<FetchPhase>>);
+
+impl Default for FetchProgress {
+    fn default() -> Self {
+        Self(Rc::new(Cell::new(FetchPhase::Running)))
+    }
+}
+
+impl FetchProgress {
+    fn is_running(&self) -> bool {
+        self.0.get() == FetchPhase::Running
+    }
+
+    fn cancel(&self) -> bool {
+        let running = self.is_running();
+        if running {
+            self.0.set(FetchPhase::Cancelled);
+        }
+        running
+    }
+
+    // `false` if cancelled before
+    pub fn complete(&self) -> bool {
+        let running = self.is_running();
+        if running {
+            self.0.set(FetchPhase::Completed);
+        }
+        running
+    }
+}
+
+#[cfg(feature = "browser")]
 pub(crate) struct PendingFetch {
     url: SmolStr,
-    #[allow(dead_code)]
     abort: Abort,
     timeout: Option<Duration>,
     request_future: JsFuture,
+    external_abort: Vec<AbortSignal>,
+    deserialize_mode: DeserializeMode,
+    // JS callbacks, which must live until the request completes
+    handlers: Option<Box<dyn Any>>,
 }
 
+#[cfg(feature = "browser")]
 impl PendingFetch {
     pub fn new(
         url: impl ToSmolStr,
@@ -77,26 +418,113 @@ impl PendingFetch {
             abort,
             timeout,
             request_future,
+            external_abort: Vec::new(),
+            deserialize_mode: DeserializeMode::Deserialize,
+            handlers: None,
         }
     }
 
-    pub async fn wait_completion(self) -> DecodedResponse<Response> {
-        match self
-            .request_future
-            .timeout(self.timeout.unwrap_or_else(|| Duration::from_secs(900)))
-            .await
-        {
+    // any of external signals aborts the request, the request itself aborts only its own
+    pub fn with_external_abort(mut self, external_abort: Option<AbortSignal>) -> Self {
+        self.external_abort.extend(external_abort);
+        self
+    }
+
+    pub fn with_deserialize_mode(mut self, deserialize_mode: DeserializeMode) -> Self {
+        self.deserialize_mode = deserialize_mode;
+        self
+    }
+
+    pub fn with_handlers(mut self, handlers: impl Any) -> Self {
+        self.handlers = Some(Box::new(handlers));
+        self
+    }
+
+    pub fn abort(&self) -> &Abort {
+        &self.abort
+    }
+
+    pub fn deserialize_mode(&self) -> DeserializeMode {
+        self.deserialize_mode
+    }
+
+    pub async fn wait_completion(mut self) -> DecodedResponse<Response> {
+        let external = std::mem::take(&mut self.external_abort);
+        if external.is_empty() {
+            self.wait_response().await
+        } else {
+            self.wait_completion_with_abort(external).await
+        }
+    }
+
+    // request aborted by any of `external` completes with `StatusCode::Aborted`
+    async fn wait_completion_with_abort(
+        self,
+        external: Vec<AbortSignal>,
+    ) -> DecodedResponse<Response> {
+        let aborted = || external.iter().any(AbortSignal::aborted);
+        if aborted() {
+            self.abort.abort();
+        }
+        let on_abort = Closure::<dyn FnMut()>::new({
+            let abort = self.abort.clone();
+            move || abort.abort()
+        });
+        let callback = on_abort.as_ref().unchecked_ref();
+        for signal in &external {
+            let _ = signal.add_event_listener_with_callback("abort", callback);
+        }
+
+        let result = self.wait_response().await;
+
+        for signal in &external {
+            let _ = signal.remove_event_listener_with_callback("abort", callback);
+        }
+        if aborted() {
+            DecodedResponse::new(StatusCode::Aborted).with_hint("Fetch aborted")
+        } else {
+            result
+        }
+    }
+
+    async fn wait_response(self) -> DecodedResponse<Response> {
+        let response = match self.timeout {
+            Some(timeout) => self.request_future.timeout(timeout).await,
+            None => Ok(self.request_future.await),
+        };
+        match response {
             Ok(Ok(response)) => {
                 let response = response.unchecked_into::<Response>();
-                if !response.ok() && matches!(response.type_(), ResponseType::Error) {
-                    DecodedResponse::new(StatusCode::FetchFailed).with_hint("Fetch network error")
-                } else {
-                    DecodedResponse::new(response.status()).with_response(response)
+                match response.type_() {
+                    ResponseType::Error if !response.ok() => {
+                        DecodedResponse::new(StatusCode::FetchFailed)
+                            .with_hint("Fetch network error")
+                    }
+                    // manual redirect hides both status and location of the response
+                    ResponseType::Opaqueredirect => DecodedResponse::new(StatusCode::Found)
+                        .with_final_url(response.url())
+                        .with_hint("Redirect not followed"),
+                    _ => DecodedResponse::new(StatusCode::from_response(&response))
+                        .with_headers(ResponseHeaders::from(&response.headers()))
+                        .with_final_url(response.url())
+                        .with_response(response),
                 }
             }
-            Ok(Err(error)) => DecodedResponse::new(StatusCode::FetchFailed).with_hint(
-                uformat_smolstr!("Fetch start failed ({})", js_error(error).as_str()),
-            ),
+            Ok(Err(error)) => {
+                let error = js_error(error);
+                // cancelled deliberately, unlike network failures it must not be retried
+                let status = if error.name() == "AbortError" {
+                    StatusCode::Aborted
+                } else {
+                    StatusCode::FetchFailed
+                };
+                DecodedResponse::new(status)
+                    .with_hint(uformat_smolstr!(
+                        "Fetch start failed ({})",
+                        error.to_string().as_str()
+                    ))
+                    .with_error(error)
+            }
             Err(_) => {
                 self.abort.abort();
                 DecodedResponse::new(StatusCode::FetchTimeout).with_hint(self.url)
@@ -108,6 +536,9 @@ impl PendingFetch {
 pub(crate) struct DecodedResponse<R> {
     status: StatusCode,
     hint: Option<SmolStr>,
+    error: Option<FetchError>,
+    final_url: Option<SmolStr>,
+    headers: ResponseHeaders,
     response: Option<R>,
 }
 
@@ -116,15 +547,34 @@ impl<R> DecodedResponse<R> {
         Self {
             status: status.into(),
             hint: None,
+            error: None,
+            final_url: None,
+            headers: ResponseHeaders::default(),
             response: None,
         }
     }
 
+    pub fn with_final_url(mut self, final_url: impl ToSmolStr) -> Self {
+        let final_url = final_url.to_smolstr();
+        self.final_url = (!final_url.is_empty()).then_some(final_url);
+        self
+    }
+
+    pub fn with_headers(mut self, headers: ResponseHeaders) -> Self {
+        self.headers = headers;
+        self
+    }
+
     pub fn with_response(mut self, response: R) -> Self {
         self.response = Some(response);
         self
     }
 
+    pub fn with_error(mut self, error: FetchError) -> Self {
+        self.error = Some(error);
+        self
+    }
+
     pub fn with_hint(mut self, hint: impl ToSmolStr) -> Self {
         self.hint = Some(hint.to_smolstr());
         self
@@ -142,10 +592,36 @@ impl<R> DecodedResponse<R> {
         self.hint.as_deref()
     }
 
-    fn as_empty<U>(self) -> DecodedResponse<U> {
+    pub fn error(&self) -> Option<&FetchError> {
+        self.error.as_ref()
+    }
+
+    pub fn final_url(&self) -> Option<&str> {
+        self.final_url.as_deref()
+    }
+
+    pub fn take_headers(&mut self) -> ResponseHeaders {
+        std::mem::take(&mut self.headers).with_final_url(self.final_url.clone())
+    }
+
+    pub fn map_response<U>(self, f: impl FnOnce(R) -> U) -> DecodedResponse<U> {
         DecodedResponse {
             status: self.status,
             hint: self.hint,
+            error: self.error,
+            final_url: self.final_url,
+            headers: self.headers,
+            response: self.response.map(f),
+        }
+    }
+
+    fn into_empty<U>(self) -> DecodedResponse<U> {
+        DecodedResponse {
+            status: self.status,
+            hint: self.hint,
+            error: self.error,
+            final_url: self.final_url,
+            headers: self.headers,
             response: None,
         }
     }
@@ -156,70 +632,146 @@ where
     R: FetchDeserializable,
     MV: MacVerify,
 {
+    execute_fetch_with_progress::<R, MV>(fetch, None).await
+}
+
+// progress of response body download is reported as a ratio, if its length is known
+pub(crate) async fn execute_fetch_with_progress<R, MV>(
+    fetch: PendingFetch,
+    progress: Option<Box<dyn FnMut(f64)>>,
+) -> DecodedResponse<R>
+where
+    R: FetchDeserializable,
+    MV: MacVerify,
+{
+    let deserialize_mode = fetch.deserialize_mode();
     let mut fetched = fetch.wait_completion().await;
     let Some(response) = fetched.take_response() else {
-        return fetched.as_empty();
+        return fetched.into_empty();
     };
 
     let status = fetched.status();
-    match status {
-        StatusCode::Ok
-        | StatusCode::Created
-        | StatusCode::BadRequest
-        | StatusCode::Forbidden
-        | StatusCode::InternalServerError
-        | StatusCode::NotFound
-        | StatusCode::Conflict
-        | StatusCode::PayloadTooBig
-        | StatusCode::RateLimited
-        | StatusCode::Unauthorized => match decode_response::<R, MV>(status, response).await {
-            Ok(result) => result,
-            Err(result) => result,
-        },
-        _ => fetched.as_empty(),
+    if !has_decodable_body(status) {
+        return fetched.into_empty();
+    }
+
+    let headers = fetched.take_headers();
+    match decode_response::<R, MV>(status, &headers, response, deserialize_mode, progress).await {
+        Ok(result) | Err(result) => result
+            .with_headers(headers)
+            .with_final_url(fetched.final_url().unwrap_or_default()),
+    }
+}
+
+// response of a failed request is decoded as R2, allowing an error body distinct from the entity
+pub(crate) async fn execute_fetch_or_error<R, R2, MV>(
+    fetch: PendingFetch,
+) -> DecodedResponse<Result<R, R2>>
+where
+    R: FetchDeserializable,
+    R2: FetchDeserializable,
+    MV: MacVerify,
+{
+    let deserialize_mode = fetch.deserialize_mode();
+    let mut fetched = fetch.wait_completion().await;
+    let Some(response) = fetched.take_response() else {
+        return fetched.into_empty();
+    };
+
+    let status = fetched.status();
+    if !has_decodable_body(status) {
+        return fetched.into_empty();
     }
+
+    let headers = fetched.take_headers();
+    let result = if status.is_failure() {
+        match decode_response::<R2, MV>(status, &headers, response, deserialize_mode, None).await {
+            Ok(result) | Err(result) => result.map_response(Err),
+        }
+    } else {
+        match decode_response::<R, MV>(status, &headers, response, deserialize_mode, None).await {
+            Ok(result) | Err(result) => result.map_response(Ok),
+        }
+    };
+    result
+        .with_headers(headers)
+        .with_final_url(fetched.final_url().unwrap_or_default())
+}
+
+// body of a successful response is returned as is, without decoding
+pub(crate) async fn execute_fetch_raw(fetch: PendingFetch) -> DecodedResponse<Vec<u8>> {
+    let mut fetched = fetch.wait_completion().await;
+    let Some(response) = fetched.take_response() else {
+        return fetched.into_empty();
+    };
+
+    let status = fetched.status();
+    if !status.is_success() || status == StatusCode::NotModified {
+        return fetched.into_empty();
+    }
+
+    let result = match response_bytes(response).await {
+        Ok(content) => DecodedResponse::new(status).with_response(content),
+        Err((status, hint)) => DecodedResponse::new(status).with_hint(hint),
+    };
+    result
+        .with_headers(fetched.take_headers())
+        .with_final_url(fetched.final_url().unwrap_or_default())
+}
+
+fn has_decodable_body(status: StatusCode) -> bool {
+    matches!(
+        status,
+        StatusCode::Ok
+            | StatusCode::Created
+            | StatusCode::Accepted
+            | StatusCode::NotModified
+            | StatusCode::BadRequest
+            | StatusCode::Forbidden
+            | StatusCode::InternalServerError
+            | StatusCode::MethodNotAllowed
+            | StatusCode::NotFound
+            | StatusCode::NotImplemented
+            | StatusCode::Conflict
+            | StatusCode::PayloadTooBig
+            | StatusCode::RateLimited
+            | StatusCode::ServiceUnavailable
+            | StatusCode::UnprocessableEntity
+            | StatusCode::Unauthorized
+    )
 }
 
 async fn decode_response<R, MV>(
     status: StatusCode,
+    headers: &ResponseHeaders,
     response: Response,
+    deserialize_mode: DeserializeMode,
+    progress: Option<Box<dyn FnMut(f64)>>,
 ) -> Result<DecodedResponse<R>, DecodedResponse<R>>
 where
     R: FetchDeserializable,
     MV: MacVerify,
 {
-    let headers = response.headers();
-    let content_type = headers.get("Content-Type").map_err(|error| {
-        DecodedResponse::new(StatusCode::FetchFailed).with_hint(uformat_smolstr!(
-            "Cannot decode Content-Type header: {}.",
-            js_error(error).as_str()
-        ))
-    })?;
-    let media_type = match content_type {
-        Some(content_type) => MediaType::from(content_type.as_str()),
+    let media_type = match headers.get("Content-Type") {
+        Some(content_type) => MediaType::from(content_type),
+        None if status == StatusCode::NotModified => return Ok(DecodedResponse::new(status)),
         None => MediaType::Plain,
     };
 
-    let signature = headers.get(HEADER_SIGNATURE).map_err(|error| {
-        DecodedResponse::new(StatusCode::FetchFailed).with_hint(uformat_smolstr!(
-            "Cannot decode {} header: {}.",
-            HEADER_SIGNATURE,
-            js_error(error).as_str()
-        ))
-    })?;
-
-    let array_promise = response
-        .array_buffer()
-        .map_err(|_| DecodedResponse::new(StatusCode::DecodeFailed).with_hint("Decode 1"))?;
-    let content_array_buffer = JsFuture::from(array_promise)
-        .await
-        .map_err(|_| DecodedResponse::new(StatusCode::DecodeFailed).with_hint("Decode 2"))?;
+    let content = match progress {
+        Some(progress) => read_body(response, headers, progress)
+            .await
+            .map_err(|error| (StatusCode::FetchFailed, error)),
+        None => response_bytes(response).await,
+    }
+    .map_err(|(status, hint)| DecodedResponse::new(status).with_hint(hint))?;
 
-    match deserialize_content::<_, MV>(
+    match deserialize_data::<_, MV>(
         media_type,
-        DeserializeMode::Deserialize,
-        content_array_buffer,
-        signature.as_deref(),
+        deserialize_mode,
+        content,
+        headers.get(HEADER_CONTENT_ENCODING),
+        headers.get(HEADER_SIGNATURE),
     ) {
         Ok(None) => Ok(DecodedResponse::new(status)),
         Ok(Some(response)) => Ok(DecodedResponse::new(status).with_response(response)),
@@ -227,46 +779,153 @@ where
     }
 }
 
+#[cfg(feature = "browser")]
+async fn response_bytes(response: Response) -> Result<Vec<u8>, (StatusCode, SmolStr)> {
+    let array_promise = response
+        .array_buffer()
+        .map_err(|_| (StatusCode::DecodeFailed, SmolStr::new_static("Decode 1")))?;
+    JsFuture::from(array_promise)
+        .await
+        .map(|content| Uint8Array::new(&content).to_vec())
+        .map_err(|_| (StatusCode::DecodeFailed, SmolStr::new_static("Decode 2")))
+}
+
+async fn read_body(
+    response: Response,
+    headers: &ResponseHeaders,
+    mut progress: Box<dyn FnMut(f64)>,
+) -> Result<Vec<u8>, SmolStr> {
+    let total = headers
+        .get(HEADER_CONTENT_LENGTH)
+        .and_then(|length| length.trim().parse::<f64>().ok())
+        .filter(|total| *total > 0.0);
+
+    let mut reader = BodyReader::new(response);
+    let mut content = Vec::new();
+    while let Some(chunk) = reader.next_chunk().await? {
+        content.extend(chunk);
+        // length of encoded content may differ from the length of read content
+        if let Some(total) = total {
+            progress((content.len() as f64 / total).min(1.0));
+        }
+    }
+    Ok(content)
+}
+
+// chunks of the response body as they arrive
+#[cfg(feature = "browser")]
+pub(crate) struct BodyReader {
+    reader: Option<ReadableStreamDefaultReader>,
+}
+
+#[cfg(feature = "browser")]
+impl BodyReader {
+    pub(crate) fn new(response: Response) -> Self {
+        Self {
+            reader: response
+                .body()
+                .map(|body| body.get_reader().unchecked_into()),
+        }
+    }
+
+    pub(crate) async fn next_chunk(&mut self) -> Result<Option<Vec<u8>>, SmolStr> {
+        let Some(reader) = &self.reader else {
+            return Ok(None);
+        };
+        let chunk = JsFuture::from(reader.read())
+            .await
+            .map_err(js_error)?
+            .unchecked_into::<ReadableStreamReadResult>();
+        if chunk.get_done().unwrap_or(true) {
+            self.reader = None;
+            return Ok(None);
+        }
+        Ok(Some(Uint8Array::new(&chunk.get_value()).to_vec()))
+    }
+}
+
 #[derive(Clone, Copy, PartialEq, Eq)]
 pub enum DecodeMode {
     Base64,
     Plain,
 }
 
+// inflated with `decompress` feature, unless the browser has already done so
+#[cfg(feature = "browser")]
 pub fn decode_content(
     mode: DecodeMode,
     content: JsValue,
+    content_encoding: Option<&str>,
 ) -> Result<Option<Vec<u8>>, (StatusCode, SmolStr)> {
-    let data = if content.is_string() {
-        if let Some(string) = content.dyn_ref::<JsString>().and_then(|s| s.as_string()) {
-            if string.is_empty() {
-                None
-            } else {
-                Some(string.as_bytes().to_vec())
-            }
-        } else {
-            None
-        }
+    decode_data(mode, content_data(content), content_encoding)
+}
+
+// content is either a string or an array buffer
+#[cfg(feature = "browser")]
+fn content_data(content: JsValue) -> Vec<u8> {
+    if content.is_string() {
+        content
+            .dyn_ref::<JsString>()
+            .and_then(|string| string.as_string())
+            .map(String::into_bytes)
+            .unwrap_or_default()
     } else {
-        // otherwise content is an array buffer
-        let array = Uint8Array::new(&content);
-        if array.length() == 0 {
-            None
-        } else {
-            Some(array.to_vec())
-        }
+        Uint8Array::new(&content).to_vec()
+    }
+}
+
+// empty content is `None`
+fn decode_data(
+    mode: DecodeMode,
+    data: Vec<u8>,
+    content_encoding: Option<&str>,
+) -> Result<Option<Vec<u8>>, (StatusCode, SmolStr)> {
+    if data.is_empty() {
+        return Ok(None);
+    }
+
+    #[cfg(not(feature = "decompress"))]
+    let _ = content_encoding;
+
+    let data = if mode == DecodeMode::Base64 {
+        general_purpose::STANDARD_NO_PAD
+            .decode(data)
+            .map_err(|error| (StatusCode::DecodeFailed, error.to_smolstr()))?
+    } else {
+        data
     };
+    #[cfg(feature = "decompress")]
+    let data = decompress(data, content_encoding)?;
+    Ok(Some(data))
+}
 
-    data.map(|data| {
-        if mode == DecodeMode::Base64 {
-            general_purpose::STANDARD_NO_PAD
-                .decode(data)
-                .map_err(|error| (StatusCode::DecodeFailed, format_smolstr!("{error}")))
-        } else {
-            Ok(data)
+#[cfg(feature = "decompress")]
+fn decompress(
+    data: Vec<u8>,
+    content_encoding: Option<&str>,
+) -> Result<Vec<u8>, (StatusCode, SmolStr)> {
+    use std::io::Read;
+
+    use flate2::read::{GzDecoder, ZlibDecoder};
+
+    let mut inflated = Vec::new();
+    let result = match (content_encoding.map(str::trim), data.as_slice()) {
+        // magic bytes are checked as the header is kept even if the browser inflated content
+        (Some(encoding), [0x1f, 0x8b, ..]) if encoding.eq_ignore_ascii_case("gzip") => {
+            GzDecoder::new(data.as_slice()).read_to_end(&mut inflated)
         }
-    })
-    .transpose()
+        (Some(encoding), [cmf, flg, ..])
+            if encoding.eq_ignore_ascii_case("deflate")
+                && cmf & 0x0f == 8
+                && (u16::from(*cmf) << 8 | u16::from(*flg)) % 31 == 0 =>
+        {
+            ZlibDecoder::new(data.as_slice()).read_to_end(&mut inflated)
+        }
+        _ => return Ok(data),
+    };
+    result
+        .map(|_| inflated)
+        .map_err(|error| (StatusCode::DecodeFailed, error.to_smolstr()))
 }
 
 #[derive(Clone, Copy, PartialEq, Eq)]
@@ -284,10 +943,32 @@ impl From<DeserializeMode> for DecodeMode {
     }
 }
 
+#[cfg(feature = "browser")]
 pub fn deserialize_content<R, MV>(
     media_type: MediaType,
     mode: DeserializeMode,
     content: JsValue,
+    content_encoding: Option<&str>,
+    signature: Option<&str>,
+) -> Result<Option<R>, (StatusCode, SmolStr)>
+where
+    R: FetchDeserializable,
+    MV: MacVerify,
+{
+    deserialize_data::<R, MV>(
+        media_type,
+        mode,
+        content_data(content),
+        content_encoding,
+        signature,
+    )
+}
+
+pub(crate) fn deserialize_data<R, MV>(
+    media_type: MediaType,
+    mode: DeserializeMode,
+    data: Vec<u8>,
+    content_encoding: Option<&str>,
     signature: Option<&str>,
 ) -> Result<Option<R>, (StatusCode, SmolStr)>
 where
@@ -295,14 +976,18 @@ where
     MV: MacVerify,
 {
     match media_type {
+        #[cfg(feature = "cbor")]
+        MediaType::Cbor => (),
         #[cfg(feature = "json")]
         MediaType::Json => (),
+        #[cfg(feature = "messagepack")]
+        MediaType::MsgPack => (),
         #[cfg(feature = "postcard")]
         MediaType::Postcard => (),
         _ => Err((StatusCode::UnsupportedMediaType, SmolStr::default()))?,
     }
 
-    let data = decode_content(mode.into(), content)?;
+    let data = decode_data(mode.into(), data, content_encoding)?;
     let Some(data) = data else {
         return Ok(None);
     };
@@ -323,8 +1008,12 @@ where
     }
 
     match media_type {
+        #[cfg(feature = "cbor")]
+        MediaType::Cbor => R::try_from_cbor(&data),
         #[cfg(feature = "json")]
         MediaType::Json => R::try_from_json(&data),
+        #[cfg(feature = "messagepack")]
+        MediaType::MsgPack => R::try_from_msgpack(&data),
         #[cfg(feature = "postcard")]
         MediaType::Postcard => R::try_from_postcard(&data),
         _ => {
@@ -335,10 +1024,111 @@ where
         }
     }
     .map_err(|error| {
-        (
-            StatusCode::DecodeFailed,
-            SmolStr::from_iter(["Deserialization failed: ", error.as_str()]),
-        )
+        let hint = if media_type.is_text() {
+            let snippet = String::from_utf8_lossy(&data[..data.len().min(DECODE_SNIPPET_LEN)]);
+            uformat_smolstr!(
+                "Deserialization failed: {}, content: {}",
+                error.as_str(),
+                &*snippet
+            )
+        } else {
+            SmolStr::from_iter(["Deserialization failed: ", error.as_str()])
+        };
+        (StatusCode::DecodeFailed, hint)
     })
     .map(|response| Some(response))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cancel_keeps_newer_transfer() {
+        let transfer_state = Mutable::new(TransferState::PendingLoad);
+        let transfer_generation = Mutable::new(0);
+        let replaced = StartedTransfer::new(transfer_state.clone(), transfer_generation.clone());
+        let current = StartedTransfer::new(transfer_state.clone(), transfer_generation.clone());
+
+        replaced.reset();
+        assert_eq!(TransferState::PendingLoad, transfer_state.get());
+
+        current.reset();
+        assert_eq!(TransferState::Empty, transfer_state.get());
+    }
+
+    #[test]
+    fn allow_skips_unknown_methods() {
+        assert_eq!(
+            vec![Method::Get, Method::Head, Method::Options],
+            allowed_methods("GET, HEAD,TRACE , options")
+        );
+        assert!(allowed_methods("").is_empty());
+    }
+
+    #[test]
+    fn retry_after_seconds() {
+        assert_eq!(
+            Some(Duration::from_secs(120)),
+            retry_after_delay(" 120 ", 0)
+        );
+        assert_eq!(None, retry_after_delay("soon", 0));
+    }
+
+    #[test]
+    fn retry_after_http_date() {
+        assert_eq!(
+            Some(784111777),
+            http_date_seconds("Sun, 06 Nov 1994 08:49:37 GMT")
+        );
+        assert_eq!(
+            Some(951782400),
+            http_date_seconds("Tue, 29 Feb 2000 00:00:00 GMT")
+        );
+        assert_eq!(
+            Some(Duration::from_secs(30)),
+            retry_after_delay("Sun, 06 Nov 1994 08:49:37 GMT", 784111747)
+        );
+        assert_eq!(
+            Some(Duration::ZERO),
+            retry_after_delay("Sun, 06 Nov 1994 08:49:37 GMT", 784111800)
+        );
+        assert_eq!(None, http_date_seconds("Sunday, 06-Nov-94 08:49:37 GMT"));
+    }
+
+    #[cfg(feature = "decompress")]
+    mod decompress {
+        use std::io::Write;
+
+        use flate2::{
+            Compression,
+            write::{GzEncoder, ZlibEncoder},
+        };
+
+        use super::*;
+
+        const CONTENT: &[u8] = br#"{"name":"fetsig"}"#;
+
+        #[test]
+        fn gzip_is_inflated() {
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(CONTENT).unwrap();
+            let data = encoder.finish().unwrap();
+            assert_eq!(CONTENT, decompress(data, Some("gzip")).unwrap());
+        }
+
+        #[test]
+        fn deflate_is_inflated() {
+            let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(CONTENT).unwrap();
+            let data = encoder.finish().unwrap();
+            assert_eq!(CONTENT, decompress(data, Some("deflate")).unwrap());
+        }
+
+        #[test]
+        fn plain_is_kept() {
+            assert_eq!(CONTENT, decompress(CONTENT.to_vec(), Some("gzip")).unwrap());
+            assert_eq!(CONTENT, decompress(CONTENT.to_vec(), None).unwrap());
+        }
+    }
+}
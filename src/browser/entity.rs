@@ -1,43 +1,69 @@
-use std::marker::PhantomData;
+use std::{cell::Cell, convert::Infallible, marker::PhantomData, rc::Rc, time::Duration};
 
-use artwrap::spawn_local;
-use futures_signals::signal::{
-    Mutable, MutableLockMut, MutableLockRef, Signal, SignalExt, and, not,
+use artwrap::sleep;
+use futures_signals::{
+    signal::{Mutable, MutableLockMut, MutableLockRef, Signal, SignalExt, and, not},
+    signal_vec::MutableVec,
 };
 use futures_signals_ext::{MutableExt, MutableOption};
 use log::{debug, error, trace, warn};
 use serde::{Serialize, de::DeserializeOwned};
 use smol_str::SmolStr;
 
+#[cfg(feature = "cbor")]
+use crate::CborSerialize;
 #[cfg(feature = "json")]
 use crate::JSONSerialize;
-#[cfg(any(feature = "json", feature = "postcard"))]
+#[cfg(any(
+    feature = "cbor",
+    feature = "json",
+    feature = "messagepack",
+    feature = "postcard"
+))]
 use crate::MediaType;
+#[cfg(feature = "messagepack")]
+use crate::MsgPackSerialize;
 #[cfg(feature = "postcard")]
 use crate::PostcardSerialize;
 use crate::{
-    Dirty, EntityResponse, HEADER_SIGNATURE, Inner, MacSign, MacVerify, Messages, NoMac, StatusCode,
+    Dirty, EntityResponse, HEADER_ETAG, HEADER_IF_NONE_MATCH, HEADER_SIGNATURE, Inner, MacSign,
+    MacVerify, Messages, NoMac, StatusCode,
 };
 
 use super::{
-    common::{PendingFetch, execute_fetch},
-    request::Request,
+    FetchError,
+    common::{
+        DecodedResponse, FetchHandle, PendingFetch, ResponseHeaders, execute_fetch,
+        execute_fetch_or_error, execute_fetch_raw, execute_fetch_with_progress,
+        next_transfer_generation,
+    },
+    request::{Method, Request},
+    spawn_local,
     transferstate::{OperationState, TransferState},
 };
 
 pub struct EntityStore<E, MV = NoMac> {
     transfer_state: Mutable<TransferState>,
+    transfer_generation: Mutable<u64>,
     messages: Messages,
     entity: MutableOption<E>,
+    etag: Mutable<Option<SmolStr>>,
+    logging: bool,
     pmv: PhantomData<MV>,
 }
 
 impl<E, MV> EntityStore<E, MV> {
+    pub const RETRY_BACKOFF: Duration = Duration::from_millis(500);
+    pub const RETRY_DELAY_MAX: Duration = Duration::from_secs(60);
+
     pub fn new(entity: Option<E>) -> Self {
         Self {
             transfer_state: Mutable::new(TransferState::Empty),
+            transfer_generation: Mutable::new(0),
             messages: Messages::new(),
             entity: MutableOption::new(entity),
+            etag: Mutable::new(None),
+            logging: true,
             pmv: PhantomData,
         }
     }
@@ -50,9 +76,22 @@ impl<E, MV> EntityStore<E, MV> {
         Self::new(Some(E::default()))
     }
 
+    // `false` disables logging of all requests of the store
+    #[must_use]
+    pub fn with_logging(mut self, logging: bool) -> Self {
+        self.logging = logging;
+        self
+    }
+
+    fn apply_logging<'a>(&self, request: Request<'a>) -> Request<'a> {
+        let logging = self.logging && request.logging();
+        request.with_logging(logging)
+    }
+
     pub fn reset(&self, entity: Option<E>) {
         self.transfer_state.set(TransferState::Empty);
         self.messages.clear_all();
+        self.etag.set(None);
         self.set(entity);
     }
 
@@ -67,6 +106,7 @@ impl<E, MV> EntityStore<E, MV> {
     pub fn replace(&self, entity: Option<E>) -> Option<E> {
         self.transfer_state.set(TransferState::Empty); // TODO: is it ok?
         self.messages.clear_all();
+        self.etag.set(None);
         self.entity.replace(entity)
     }
 
@@ -101,10 +141,37 @@ impl<E, MV> EntityStore<E, MV> {
         self.transfer_state.set_neq(transfer_state);
     }
 
+    // observing stops when the subscription is dropped
+    pub fn on_transfer_state_change<F>(&self, mut f: F) -> Subscription
+    where
+        F: FnMut(TransferState) + 'static,
+    {
+        let subscription = Subscription::new();
+        let changes = subscription.until_dropped(self.transfer_state.signal().dedupe());
+        spawn_local(changes.for_each({
+            let active = subscription.active();
+            move |transfer_state| {
+                if active.get() {
+                    f(transfer_state);
+                }
+                async {}
+            }
+        }));
+        subscription
+    }
+
     pub fn reset_transfer_error(&self) {
         self.transfer_state.lock_mut().reset_error();
     }
 
+    pub fn etag(&self) -> Option<SmolStr> {
+        self.etag.get_cloned()
+    }
+
+    pub fn set_etag(&self, etag: Option<SmolStr>) {
+        self.etag.set(etag);
+    }
+
     #[inline]
     pub fn loaded(&self) -> bool {
         self.transfer_state.map(TransferState::loaded)
@@ -116,6 +183,17 @@ impl<E, MV> EntityStore<E, MV> {
             .dedupe()
     }
 
+    // stays `true` during later reloads, observed from the moment of subscription
+    pub fn first_loaded_signal(&self) -> impl Signal<Item = bool> + use<E, MV> {
+        let mut loaded = false;
+        self.transfer_state
+            .signal_ref(move |state| {
+                loaded |= state.loaded();
+                loaded
+            })
+            .dedupe()
+    }
+
     #[inline]
     pub fn loaded_state(&self) -> OperationState {
         self.transfer_state.get().as_load()
@@ -173,6 +251,16 @@ impl<E, MV> EntityStore<E, MV> {
             .dedupe()
     }
 
+    pub fn error_status(&self) -> Option<StatusCode> {
+        self.transfer_state.map(TransferState::error_status)
+    }
+
+    pub fn error_status_signal(&self) -> impl Signal<Item = Option<StatusCode>> + use<E, MV> {
+        self.transfer_state
+            .signal_ref(TransferState::error_status)
+            .dedupe()
+    }
+
     #[inline]
     pub fn pending(&self) -> bool {
         self.transfer_state.map(TransferState::pending)
@@ -215,6 +303,20 @@ impl<E, MV> EntityStore<E, MV> {
         and(self.dirty_signal(), not(self.messages_error_signal())).dedupe()
     }
 
+    pub fn dirty(&self) -> bool
+    where
+        E: Dirty,
+    {
+        self.entity.lock_ref().as_ref().is_some_and(Dirty::is_dirty)
+    }
+
+    pub fn can_commit(&self) -> bool
+    where
+        E: Dirty,
+    {
+        self.dirty() && !self.messages.error()
+    }
+
     #[inline]
     pub fn signal_map<F, U>(&self, f: F) -> impl Signal<Item = U> + use<E, MV, F, U>
     where
@@ -380,9 +482,12 @@ impl<E, MV> EntityStore<E, MV> {
     }
 
     pub fn set_externally_loaded(&self, entity: Option<E>) {
+        self.set_externally_loaded_with_status(entity, StatusCode::Ok);
+    }
+
+    pub fn set_externally_loaded_with_status(&self, entity: Option<E>, status: StatusCode) {
         self.entity.set(entity);
-        self.transfer_state
-            .set_neq(TransferState::Loaded(StatusCode::Ok));
+        self.transfer_state.set_neq(TransferState::Loaded(status));
     }
 
     pub fn set_inner<I>(&self, entity: Option<I>)
@@ -405,17 +510,62 @@ impl<E, MV> EntityStore<E, MV> {
     {
         self.set_externally_loaded(entity.map(E::from_inner));
     }
+
+    // e.g. to be serialized into server rendered HTML and passed to `hydrate`
+    pub fn snapshot(&self) -> (Option<E>, TransferState, Messages)
+    where
+        E: Clone,
+    {
+        let messages = self
+            .messages
+            .lock_ref()
+            .iter()
+            .map(|(key, messages)| {
+                (
+                    key.clone(),
+                    MutableVec::new_with_values(messages.lock_ref().to_vec()),
+                )
+            })
+            .collect();
+        (
+            self.entity.get_cloned(),
+            self.transfer_state.get(),
+            Messages::from_inner(messages),
+        )
+    }
+
+    // a pending transfer cannot be resumed, it is restored as empty
+    pub fn hydrate(&self, snapshot: (Option<E>, TransferState, Messages)) {
+        let (entity, transfer_state, messages) = snapshot;
+        self.entity.set(entity);
+        self.transfer_state.set(if transfer_state.pending() {
+            TransferState::Empty
+        } else {
+            transfer_state
+        });
+        self.messages.replace(messages);
+    }
 }
 
 impl<E, MV> EntityStore<E, MV>
 where
     MV: MacVerify,
 {
-    pub fn load<C>(&self, request: Request<'_>, result_callback: C)
+    // on a cache hit the callback gets the status of the previous load
+    pub fn load<C>(&self, request: Request<'_>, result_callback: C) -> FetchHandle
     where
         E: DeserializeOwned + 'static,
         C: FnOnce(StatusCode) + 'static,
     {
+        self.load_with_headers(request, move |status, _| result_callback(status))
+    }
+
+    pub fn load_with_headers<C>(&self, request: Request<'_>, result_callback: C) -> FetchHandle
+    where
+        E: DeserializeOwned + 'static,
+        C: FnOnce(StatusCode, ResponseHeaders) + 'static,
+    {
+        let request = self.apply_logging(request);
         if self.transfer_state.map(TransferState::loaded) {
             if request.logging() {
                 debug!("Request to load {} skipped, using cache", request.url());
@@ -427,16 +577,133 @@ where
                     );
                 }
             }
+            result_callback(
+                self.loaded_status().unwrap_or(StatusCode::Ok),
+                ResponseHeaders::default(),
+            );
+            FetchHandle::completed()
         } else {
-            self.load_skip_cache(request, result_callback);
+            self.fetch_load(request, result_callback)
         }
     }
 
-    pub fn load_skip_cache<C>(&self, request: Request<'_>, result_callback: C)
+    pub fn load_skip_cache<C>(&self, request: Request<'_>, result_callback: C) -> FetchHandle
     where
         E: DeserializeOwned + 'static,
         C: FnOnce(StatusCode) + 'static,
     {
+        self.fetch_load(request, move |status, _| result_callback(status))
+    }
+
+    // a tick is skipped while another request is pending
+    pub fn poll<F, C>(
+        &self,
+        request_factory: F,
+        interval: Duration,
+        result_callback: C,
+    ) -> Subscription
+    where
+        E: DeserializeOwned + 'static,
+        MV: 'static,
+        F: Fn() -> Request<'static> + 'static,
+        C: Fn(StatusCode) + 'static,
+    {
+        let subscription = Subscription::new();
+        let store = self.share();
+        let result_callback = Rc::new(result_callback);
+
+        spawn_local({
+            let active = subscription.active();
+            async move {
+                loop {
+                    sleep(interval).await;
+                    let ticked = store.poll_tick(&active, || {
+                        let result_callback = result_callback.clone();
+                        store
+                            .load_skip_cache(request_factory(), move |status| {
+                                result_callback(status)
+                            })
+                            .detach();
+                    });
+                    if !ticked {
+                        break;
+                    }
+                }
+            }
+        });
+
+        subscription
+    }
+
+    // `false` once polling is stopped, `load` is skipped while another request is pending
+    fn poll_tick(&self, active: &Cell<bool>, load: impl FnOnce()) -> bool {
+        if !active.get() {
+            return false;
+        }
+        if !self.pending() {
+            load();
+        }
+        true
+    }
+
+    // `Retry-After` or doubling backoff, both capped, the callback is not called once dropped
+    pub fn load_with_retry<F, C>(
+        &self,
+        request_factory: F,
+        max_attempts: u32,
+        result_callback: C,
+    ) -> RetryHandle
+    where
+        E: DeserializeOwned + 'static,
+        MV: 'static,
+        F: Fn() -> Request<'static> + 'static,
+        C: FnOnce(StatusCode) + 'static,
+    {
+        let subscription = Subscription::new();
+        let active = subscription.active();
+        let store = self.share();
+        let mut backoff = Self::RETRY_BACKOFF;
+        spawn_local(async move {
+            let mut attempt = 1;
+            loop {
+                let (status, headers) = fetch_async::<_, MV>(
+                    store.apply_logging(request_factory()).with_is_load(true),
+                    store.transfer_state.clone(),
+                    store.transfer_generation.clone(),
+                    store.messages.clone(),
+                    Some(store.entity.clone()),
+                )
+                .await;
+                if !active.get() {
+                    break;
+                }
+                if !status.is_retryable() || attempt >= max_attempts {
+                    result_callback(status);
+                    break;
+                }
+                sleep(retry_delay(
+                    headers.retry_after(),
+                    backoff,
+                    Self::RETRY_DELAY_MAX,
+                ))
+                .await;
+                if !active.get() {
+                    break;
+                }
+                backoff = backoff.saturating_mul(2).min(Self::RETRY_DELAY_MAX);
+                attempt += 1;
+            }
+        });
+
+        subscription
+    }
+
+    fn fetch_load<C>(&self, request: Request<'_>, result_callback: C) -> FetchHandle
+    where
+        E: DeserializeOwned + 'static,
+        C: FnOnce(StatusCode, ResponseHeaders) + 'static,
+    {
+        let request = self.apply_logging(request);
         if request.logging() {
             debug!("Request to load {}", request.url());
 
@@ -448,13 +715,336 @@ where
             }
         }
 
+        let request = match self.etag.get_cloned() {
+            Some(etag) => request.with_header(HEADER_IF_NONE_MATCH, etag),
+            None => request,
+        };
+
+        let etag = self.etag.clone();
         fetch::<_, _, MV>(
             request.with_is_load(true),
             self.transfer_state.clone(),
+            self.transfer_generation.clone(),
             self.messages.clone(),
             Some(self.entity.clone()),
+            move |status, headers| {
+                if status.is_success() && status != StatusCode::NotModified {
+                    etag.set(headers.get(HEADER_ETAG).map(SmolStr::from));
+                }
+                result_callback(status, headers);
+            },
+        )
+    }
+
+    // failed response is decoded into `error_body`
+    pub fn load_with_error_body<ER, C>(
+        &self,
+        request: Request<'_>,
+        error_body: MutableOption<ER>,
+        result_callback: C,
+    ) -> FetchHandle
+    where
+        E: DeserializeOwned + 'static,
+        ER: DeserializeOwned + 'static,
+        C: FnOnce(StatusCode) + 'static,
+    {
+        let request = self.apply_logging(request).with_is_load(true);
+        let logging = request.logging();
+        if logging {
+            debug!("Request to load {}", request.url());
+
+            if !request.method().is_load() {
+                warn!(
+                    "Load request unexpectedly uses store verb {:?}",
+                    request.method().as_str()
+                );
+            }
+        }
+
+        if !start_transfer(&request, &self.transfer_state) {
+            return FetchHandle::completed();
+        }
+
+        let pending_fetch = match request.start() {
+            Ok(future) => future,
+            Err(error) => {
+                if logging {
+                    debug!("Request failed at init, error: {error}");
+                }
+                result_callback(StatusCode::BadRequest);
+                self.transfer_state.lock_mut().stop(StatusCode::FetchFailed);
+                return FetchHandle::completed();
+            }
+        };
+
+        error_body.set(None);
+        let context = EntityFetchContext {
+            logging,
+            empty_means_none: request.empty_means_none(),
+            messages: self.messages.clone(),
+            storage_entity: Some(self.entity.clone()),
+        };
+
+        let transfer_state = self.transfer_state.clone();
+        let handle = FetchHandle::new(
+            pending_fetch.abort().clone(),
+            transfer_state.clone(),
+            self.transfer_generation.clone(),
+        );
+        let progress = handle.progress();
+
+        spawn_local(async move {
+            let result = execute_fetch_or_error::<EntityResponse<E>, ER, MV>(pending_fetch).await;
+            let (status, _) = complete_entity_fetch(result, context, Some(error_body));
+            if progress.complete() {
+                result_callback(status);
+                transfer_state.lock_mut().stop(status);
+            }
+        });
+
+        handle
+    }
+
+    // paging of the envelope is dropped
+    pub fn load_enveloped<R, C>(&self, request: Request<'_>, result_callback: C) -> FetchHandle
+    where
+        E: DeserializeOwned + 'static,
+        R: DeserializeOwned + Into<EntityResponse<E>> + 'static,
+        C: FnOnce(StatusCode) + 'static,
+    {
+        let request = self.apply_logging(request).with_is_load(true);
+        let logging = request.logging();
+        if logging {
+            debug!("Request to load {}", request.url());
+        }
+
+        self.spawn_fetch(
+            request,
+            None,
+            |pending_fetch, context| async move {
+                let result = execute_fetch::<R, MV>(pending_fetch)
+                    .await
+                    .map_response(|response| Ok::<_, Infallible>(response.into()));
+                let (status, _) = complete_entity_fetch(result, context, None);
+                (status, ())
+            },
+            move |status, ()| result_callback(status),
+        )
+    }
+
+    // progress is not reported without `Content-Length`
+    pub fn load_with_progress<P, C>(
+        &self,
+        request: Request<'_>,
+        progress: P,
+        result_callback: C,
+    ) -> FetchHandle
+    where
+        E: DeserializeOwned + 'static,
+        P: FnMut(f64) + 'static,
+        C: FnOnce(StatusCode) + 'static,
+    {
+        let request = self.apply_logging(request).with_is_load(true);
+        let logging = request.logging();
+        if logging {
+            debug!("Request to load {}", request.url());
+
+            if !request.method().is_load() {
+                warn!(
+                    "Load request unexpectedly uses store verb {:?}",
+                    request.method().as_str()
+                );
+            }
+        }
+
+        self.spawn_fetch(
+            request,
+            None,
+            |pending_fetch, context| async move {
+                let result = execute_fetch_with_progress::<EntityResponse<E>, MV>(
+                    pending_fetch,
+                    Some(Box::new(progress)),
+                )
+                .await
+                .map_response(Ok::<_, Infallible>);
+                let (status, _) = complete_entity_fetch(result, context, None);
+                (status, ())
+            },
+            move |status, ()| result_callback(status),
+        )
+    }
+
+    // the entity is left untouched
+    pub fn load_raw<C>(&self, request: Request<'_>, result_callback: C) -> FetchHandle
+    where
+        C: FnOnce(StatusCode, Option<Vec<u8>>) + 'static,
+    {
+        let request = self.apply_logging(request).with_is_load(true);
+        let logging = request.logging();
+        if logging {
+            debug!("Request to load raw {}", request.url());
+        }
+
+        self.spawn_fetch(
+            request,
+            None,
+            |pending_fetch, context| async move {
+                let mut result = execute_fetch_raw(pending_fetch).await;
+                let status = result.status();
+                if context.logging
+                    && let Some(hint) = result.hint()
+                {
+                    debug!("Raw request completed with {status}, hint: {hint}");
+                }
+                (status, result.take_response())
+            },
             result_callback,
+        )
+    }
+
+    // transfer state is restored once completed, it does not count as a load
+    pub fn head<C>(&self, request: Request<'_>, result_callback: C) -> FetchHandle
+    where
+        C: FnOnce(StatusCode, ResponseHeaders) + 'static,
+    {
+        self.fetch_headers(request.head(), result_callback)
+    }
+
+    pub fn options<C>(&self, request: Request<'_>, result_callback: C) -> FetchHandle
+    where
+        C: FnOnce(StatusCode, Vec<Method>) + 'static,
+    {
+        self.fetch_headers(request.options(), move |status, headers| {
+            result_callback(status, headers.allow())
+        })
+    }
+
+    fn fetch_headers<C>(&self, request: Request<'_>, result_callback: C) -> FetchHandle
+    where
+        C: FnOnce(StatusCode, ResponseHeaders) + 'static,
+    {
+        let request = self.apply_logging(request).with_is_load(true);
+        let logging = request.logging();
+        if logging {
+            debug!("Request to {} {}", request.method().as_str(), request.url());
+        }
+
+        self.spawn_fetch(
+            request,
+            Some(self.transfer_state.get()),
+            |pending_fetch, context| async move {
+                let mut result = pending_fetch.wait_completion().await;
+                let status = result.status();
+                if context.logging
+                    && let Some(hint) = result.hint()
+                {
+                    debug!("Request completed with {status}, hint: {hint}");
+                }
+                (status, result.take_headers())
+            },
+            result_callback,
+        )
+    }
+
+    // Starts the transfer and runs `execute` in background. The transfer state is stopped with
+    // the resulting status, or set back to `restore_state` if given.
+    fn spawn_fetch<T, X, F, C>(
+        &self,
+        request: Request<'_>,
+        restore_state: Option<TransferState>,
+        execute: X,
+        result_callback: C,
+    ) -> FetchHandle
+    where
+        T: Default,
+        X: FnOnce(PendingFetch, EntityFetchContext<E>) -> F,
+        F: Future<Output = (StatusCode, T)> + 'static,
+        C: FnOnce(StatusCode, T) + 'static,
+    {
+        if !start_transfer(&request, &self.transfer_state) {
+            return FetchHandle::completed();
+        }
+
+        let transfer_state = self.transfer_state.clone();
+        let finish = move |status| match restore_state {
+            Some(previous_state) => transfer_state.set(previous_state),
+            None => transfer_state.lock_mut().stop(status),
+        };
+
+        let logging = request.logging();
+        let pending_fetch = match request.start() {
+            Ok(future) => future,
+            Err(error) => {
+                if logging {
+                    debug!("Request failed at init, error: {error}");
+                }
+                result_callback(StatusCode::BadRequest, T::default());
+                finish(StatusCode::FetchFailed);
+                return FetchHandle::completed();
+            }
+        };
+
+        let context = EntityFetchContext {
+            logging,
+            empty_means_none: request.empty_means_none(),
+            messages: self.messages.clone(),
+            storage_entity: Some(self.entity.clone()),
+        };
+
+        let handle = FetchHandle::new(
+            pending_fetch.abort().clone(),
+            self.transfer_state.clone(),
+            self.transfer_generation.clone(),
         );
+        let progress = handle.progress();
+        let execution = execute(pending_fetch, context);
+
+        spawn_local(async move {
+            let (status, value) = execution.await;
+            if progress.complete() {
+                result_callback(status, value);
+                finish(status);
+            }
+        });
+
+        handle
+    }
+
+    // `StatusCode::Undefined` when deduplicated request is skipped
+    pub async fn load_async(&self, request: Request<'_>) -> StatusCode
+    where
+        E: DeserializeOwned,
+    {
+        let request = self.apply_logging(request);
+        if let Some(status) = self.transfer_state.map(TransferState::loaded_status)
+            && status.is_success()
+        {
+            if request.logging() {
+                debug!("Request to load {} skipped, using cache", request.url());
+            }
+            return status;
+        }
+
+        if request.logging() {
+            debug!("Request to load {}", request.url());
+        }
+        let request = match self.etag.get_cloned() {
+            Some(etag) => request.with_header(HEADER_IF_NONE_MATCH, etag),
+            None => request,
+        };
+
+        let (status, headers) = fetch_async::<_, MV>(
+            request.with_is_load(true),
+            self.transfer_state.clone(),
+            self.transfer_generation.clone(),
+            self.messages.clone(),
+            Some(self.entity.clone()),
+        )
+        .await;
+        if status.is_success() && status != StatusCode::NotModified {
+            self.etag.set(headers.get(HEADER_ETAG).map(SmolStr::from));
+        }
+        status
     }
 
     pub fn load_with_request<MS, R, C>(
@@ -462,26 +1052,30 @@ where
         request: Request<'_>,
         request_entity: MutableOption<R>,
         result_callback: C,
-    ) where
+    ) -> FetchHandle
+    where
         E: DeserializeOwned + 'static,
         MS: MacSign,
         R: Serialize,
         C: FnOnce(StatusCode) + 'static,
     {
+        let request = self.apply_logging(request);
         store::<_, _, _, MS, MV>(
             request.with_is_load(true),
             self.transfer_state.clone(),
+            self.transfer_generation.clone(),
             self.messages.clone(),
             request_entity,
             Some(self.entity.clone()),
-            result_callback,
-        );
+            move |status, _| result_callback(status),
+        )
     }
 
-    pub fn execute<C>(&self, request: Request<'_>, result_callback: C)
+    pub fn execute<C>(&self, request: Request<'_>, result_callback: C) -> FetchHandle
     where
         C: FnOnce(StatusCode) + 'static,
     {
+        let request = self.apply_logging(request);
         if request.logging() {
             debug!("Request to execute {}", request.url());
 
@@ -496,10 +1090,11 @@ where
         fetch::<SmolStr, _, MV>(
             request.with_is_load(false),
             self.transfer_state.clone(),
+            self.transfer_generation.clone(),
             self.messages.clone(),
             None,
-            result_callback,
-        );
+            move |status, _| result_callback(status),
+        )
     }
 
     pub fn execute_with_response<R, C>(
@@ -507,10 +1102,12 @@ where
         request: Request<'_>,
         response_entity: MutableOption<R>,
         result_callback: C,
-    ) where
+    ) -> FetchHandle
+    where
         R: DeserializeOwned + 'static,
         C: FnOnce(StatusCode) + 'static,
     {
+        let request = self.apply_logging(request);
         if request.logging() {
             debug!("Request to execute {}", request.url());
 
@@ -529,18 +1126,82 @@ where
         fetch::<_, _, MV>(
             request.with_is_load(false),
             self.transfer_state.clone(),
+            self.transfer_generation.clone(),
             self.messages.clone(),
             Some(response_entity),
-            result_callback,
-        );
+            move |status, _| result_callback(status),
+        )
+    }
+
+    // stores once unchanged for `debounce`, provided it is dirty and without errors
+    pub fn enable_autosave<MS, F, C>(
+        &self,
+        request_factory: F,
+        debounce: Duration,
+        result_callback: C,
+    ) -> Subscription
+    where
+        E: Dirty + Serialize + DeserializeOwned + 'static,
+        MS: MacSign + 'static,
+        MV: 'static,
+        F: Fn() -> Request<'static> + 'static,
+        C: Fn(StatusCode) + 'static,
+    {
+        let subscription = Subscription::new();
+        let generation = Rc::new(Cell::new(0u64));
+        let store = Rc::new(self.share());
+        let request_factory = Rc::new(request_factory);
+        let result_callback = Rc::new(result_callback);
+
+        let changes = subscription.until_dropped(self.entity.signal_ref(|_| ()));
+        spawn_local(changes.for_each({
+            let active = subscription.active();
+            move |_| {
+                let current = generation.get().wrapping_add(1);
+                generation.set(current);
+
+                let active = active.clone();
+                let generation = generation.clone();
+                let store = store.clone();
+                let request_factory = request_factory.clone();
+                let result_callback = result_callback.clone();
+                spawn_local(async move {
+                    sleep(debounce).await;
+                    if active.get()
+                        && generation.get() == current
+                        && !store.pending()
+                        && store.can_commit()
+                    {
+                        store
+                            .store::<MS, _>(request_factory(), move |status| {
+                                result_callback(status)
+                            })
+                            .detach();
+                    }
+                });
+                async {}
+            }
+        }));
+
+        subscription
     }
 
-    pub fn store<MS, C>(&self, request: Request<'_>, result_callback: C)
+    pub fn store<MS, C>(&self, request: Request<'_>, result_callback: C) -> FetchHandle
     where
         E: Serialize + DeserializeOwned + 'static,
         MS: MacSign,
         C: FnOnce(StatusCode) + 'static,
     {
+        self.store_with_headers::<MS, _>(request, move |status, _| result_callback(status))
+    }
+
+    pub fn store_with_headers<MS, C>(&self, request: Request<'_>, result_callback: C) -> FetchHandle
+    where
+        E: Serialize + DeserializeOwned + 'static,
+        MS: MacSign,
+        C: FnOnce(StatusCode, ResponseHeaders) + 'static,
+    {
+        let request = self.apply_logging(request);
         let response_entity = if request.wants_response() {
             Some(self.entity.clone())
         } else {
@@ -549,6 +1210,7 @@ where
         store::<_, _, _, MS, MV>(
             request.with_is_load(false),
             self.transfer_state.clone(),
+            self.transfer_generation.clone(),
             self.messages.clone(),
             self.entity.clone(),
             response_entity,
@@ -556,41 +1218,172 @@ where
         )
     }
 
+    // the response is still decoded into the entity if requested
+    pub fn store_raw<MS, C>(
+        &self,
+        request: Request<'_>,
+        bytes: Vec<u8>,
+        result_callback: C,
+    ) -> FetchHandle
+    where
+        E: DeserializeOwned + 'static,
+        MS: MacSign,
+        C: FnOnce(StatusCode) + 'static,
+    {
+        let mut request = self.apply_logging(request).with_is_load(false);
+        if request.logging() {
+            debug!("Request to store raw {}", request.url());
+        }
+        if let Some(signature) = MS::sign(&bytes) {
+            request = request.with_header(HEADER_SIGNATURE, signature);
+        }
+
+        let response_entity = request.wants_response().then(|| self.entity.clone());
+        fetch::<_, _, MV>(
+            request.with_body(bytes),
+            self.transfer_state.clone(),
+            self.transfer_generation.clone(),
+            self.messages.clone(),
+            response_entity,
+            move |status, _| result_callback(status),
+        )
+    }
+
+    // the entity is forgotten on `Ok` or `NoContent`
+    pub fn delete<C>(&self, request: Request<'_>, result_callback: C) -> FetchHandle
+    where
+        E: 'static,
+        C: FnOnce(StatusCode) + 'static,
+    {
+        let request = self.delete_request(request);
+        if request.logging() {
+            debug!("Request to delete {}", request.url());
+        }
+
+        let entity = self.entity.clone();
+        fetch::<SmolStr, _, MV>(
+            request,
+            self.transfer_state.clone(),
+            self.transfer_generation.clone(),
+            self.messages.clone(),
+            None,
+            move |status, _| {
+                if matches!(status, StatusCode::Ok | StatusCode::NoContent) {
+                    entity.set(None);
+                }
+                result_callback(status)
+            },
+        )
+    }
+
+    // no body, the entity is neither required nor serialized
+    fn delete_request<'a>(&self, request: Request<'a>) -> Request<'a> {
+        self.apply_logging(request).delete().with_is_load(false)
+    }
+
+    pub async fn store_async<MS>(&self, request: Request<'_>) -> StatusCode
+    where
+        E: Serialize + DeserializeOwned,
+        MS: MacSign,
+    {
+        let request = self.apply_logging(request);
+        let response_entity = if request.wants_response() {
+            Some(self.entity.clone())
+        } else {
+            None
+        };
+        match prepare_store::<_, MS>(
+            request.with_is_load(false),
+            &self.transfer_state,
+            &self.messages,
+            &self.entity,
+            response_entity.is_some(),
+        ) {
+            Ok(request) => {
+                fetch_async::<_, MV>(
+                    request,
+                    self.transfer_state.clone(),
+                    self.transfer_generation.clone(),
+                    self.messages.clone(),
+                    response_entity,
+                )
+                .await
+                .0
+            }
+            Err(status) => status,
+        }
+    }
+
     pub fn store_with_response<MS, R, C>(
         &self,
         request: Request<'_>,
         response_entity: MutableOption<R>,
         result_callback: C,
-    ) where
+    ) -> FetchHandle
+    where
         E: Serialize,
         MS: MacSign,
         R: DeserializeOwned + 'static,
         C: FnOnce(StatusCode) + 'static,
     {
+        let request = self.apply_logging(request);
         store::<_, _, _, MS, MV>(
             request.with_is_load(false),
             self.transfer_state.clone(),
+            self.transfer_generation.clone(),
             self.messages.clone(),
             self.entity.clone(),
             Some(response_entity),
-            result_callback,
-        );
+            move |status, _| result_callback(status),
+        )
     }
 }
 
 fn store<E, R, C, MS, MV>(
-    mut request: Request<'_>,
+    request: Request<'_>,
     transfer_state: Mutable<TransferState>,
+    transfer_generation: Mutable<u64>,
     messages: Messages,
     request_entity: MutableOption<E>,
     storage_entity: Option<MutableOption<R>>,
     result_callback: C,
-) where
+) -> FetchHandle
+where
     E: Serialize,
     R: DeserializeOwned + 'static,
-    C: FnOnce(StatusCode) + 'static,
+    C: FnOnce(StatusCode, ResponseHeaders) + 'static,
     MS: MacSign,
     MV: MacVerify,
+{
+    match prepare_store::<_, MS>(
+        request,
+        &transfer_state,
+        &messages,
+        &request_entity,
+        storage_entity.is_some(),
+    ) {
+        Ok(request) => fetch::<_, _, MV>(
+            request,
+            transfer_state,
+            transfer_generation,
+            messages,
+            storage_entity,
+            result_callback,
+        ),
+        Err(_) => FetchHandle::completed(),
+    }
+}
+
+fn prepare_store<'a, E, MS>(
+    mut request: Request<'a>,
+    transfer_state: &Mutable<TransferState>,
+    messages: &Messages,
+    request_entity: &MutableOption<E>,
+    has_storage_entity: bool,
+) -> Result<Request<'a>, StatusCode>
+where
+    E: Serialize,
+    MS: MacSign,
 {
     if request.logging() {
         debug!("Request to store {}", request.url());
@@ -602,14 +1395,18 @@ fn store<E, R, C, MS, MV>(
             );
         }
 
-        if storage_entity.is_none() && request.wants_response() {
+        if !has_storage_entity && request.wants_response() {
             warn!("Store request wants response but defines no response entity",);
         }
     }
 
     let media_type = match request.media_type() {
+        #[cfg(feature = "cbor")]
+        Some(media_type @ MediaType::Cbor) => media_type,
         #[cfg(feature = "json")]
         Some(media_type @ MediaType::Json) => media_type,
+        #[cfg(feature = "messagepack")]
+        Some(media_type @ MediaType::MsgPack) => media_type,
         #[cfg(feature = "postcard")]
         Some(media_type @ MediaType::Postcard) => media_type,
         _ => {
@@ -622,7 +1419,7 @@ fn store<E, R, C, MS, MV>(
             transfer_state
                 .lock_mut()
                 .stop(StatusCode::UnsupportedMediaType);
-            return;
+            return Err(StatusCode::UnsupportedMediaType);
         }
     };
 
@@ -634,17 +1431,21 @@ fn store<E, R, C, MS, MV>(
                 if request.logging() {
                     error!("Cannot store nonexisting entity, unexpected code flow");
                 }
-                return;
+                return Err(StatusCode::BadRequest);
             }
+            #[cfg(feature = "cbor")]
+            (Some(content), MediaType::Cbor) => content.to_cbor(),
             #[cfg(feature = "json")]
             (Some(content), MediaType::Json) => content.to_json(),
+            #[cfg(feature = "messagepack")]
+            (Some(content), MediaType::MsgPack) => content.to_msgpack(),
             #[cfg(feature = "postcard")]
             (Some(content), MediaType::Postcard) => content.to_postcard(),
             _ => {
                 if request.logging() {
                     error!("Unsupported media type requested, unexpected code flow");
                 }
-                return;
+                return Err(StatusCode::BadRequest);
             }
         };
         let bytes = match bytes {
@@ -653,7 +1454,7 @@ fn store<E, R, C, MS, MV>(
                 if request.logging() {
                     error!("Cannot serialize entity: {error}");
                 }
-                return;
+                return Err(StatusCode::BadRequest);
             }
         };
 
@@ -664,72 +1465,171 @@ fn store<E, R, C, MS, MV>(
         request = request.with_body(bytes);
     }
 
-    fetch::<_, _, MV>(
-        request,
+    Ok(request)
+}
+
+pub(super) fn fetch<R, C, MV>(
+    request: Request<'_>,
+    transfer_state: Mutable<TransferState>,
+    transfer_generation: Mutable<u64>,
+    messages: Messages,
+    storage_entity: Option<MutableOption<R>>,
+    result_callback: C,
+) -> FetchHandle
+where
+    C: FnOnce(StatusCode, ResponseHeaders) + 'static,
+    R: DeserializeOwned + 'static,
+    MV: MacVerify,
+{
+    if !start_transfer(&request, &transfer_state) {
+        return FetchHandle::completed();
+    }
+
+    fetch_started::<_, _, MV>(
+        &request,
+        request.start(),
         transfer_state,
+        transfer_generation,
         messages,
         storage_entity,
         result_callback,
-    );
+    )
 }
 
-pub(super) fn fetch<R, C, MV>(
+pub(super) fn start_transfer(
+    request: &Request<'_>,
+    transfer_state: &Mutable<TransferState>,
+) -> bool {
+    let started = transfer_state
+        .lock_mut()
+        .try_start(request.is_load(), request.dedupe_inflight());
+    if !started && request.logging() {
+        debug!(
+            "Request to {} skipped, another request is pending",
+            request.url()
+        );
+    }
+    started
+}
+
+async fn fetch_async<R, MV>(
     request: Request<'_>,
     transfer_state: Mutable<TransferState>,
+    transfer_generation: Mutable<u64>,
+    messages: Messages,
+    storage_entity: Option<MutableOption<R>>,
+) -> (StatusCode, ResponseHeaders)
+where
+    R: DeserializeOwned,
+    MV: MacVerify,
+{
+    if !start_transfer(&request, &transfer_state) {
+        return (StatusCode::Undefined, ResponseHeaders::default());
+    }
+    next_transfer_generation(&transfer_generation);
+
+    let logging = request.logging();
+    let pending_fetch = match request.start() {
+        Ok(future) => future,
+        Err(error) => {
+            if logging {
+                debug!("Request failed at init, error: {error}");
+            }
+            transfer_state.lock_mut().stop(StatusCode::FetchFailed);
+            return (StatusCode::BadRequest, ResponseHeaders::default());
+        }
+    };
+
+    let context = EntityFetchContext {
+        logging,
+        empty_means_none: request.empty_means_none(),
+        messages,
+        storage_entity,
+    };
+    let (status, headers) = execute_entity_fetch::<_, MV>(pending_fetch, context).await;
+    transfer_state.lock_mut().stop(status);
+    (status, headers)
+}
+
+// transfer_state must be already started by the caller
+pub(super) fn fetch_started<R, C, MV>(
+    request: &Request<'_>,
+    pending_fetch: Result<PendingFetch, SmolStr>,
+    transfer_state: Mutable<TransferState>,
+    transfer_generation: Mutable<u64>,
     messages: Messages,
     storage_entity: Option<MutableOption<R>>,
     result_callback: C,
-) where
-    C: FnOnce(StatusCode) + 'static,
+) -> FetchHandle
+where
+    C: FnOnce(StatusCode, ResponseHeaders) + 'static,
     R: DeserializeOwned + 'static,
     MV: MacVerify,
 {
     let logging = request.logging();
 
-    let pending_fetch = match request.start() {
+    let pending_fetch = match pending_fetch {
         Ok(future) => future,
         Err(error) => {
             if logging {
                 debug!("Request failed at init, error: {error}");
             }
-            result_callback(StatusCode::BadRequest);
+            result_callback(StatusCode::BadRequest, ResponseHeaders::default());
             transfer_state.lock_mut().stop(StatusCode::FetchFailed);
-            return;
+            return FetchHandle::completed();
         }
     };
-    if request.is_load() {
-        transfer_state.lock_mut().start_load();
-    } else {
-        transfer_state.lock_mut().start_store();
-    }
 
     let context = EntityFetchContext {
         logging,
+        empty_means_none: request.empty_means_none(),
         messages,
         storage_entity,
     };
 
+    let handle = FetchHandle::new(
+        pending_fetch.abort().clone(),
+        transfer_state.clone(),
+        transfer_generation,
+    );
+    let progress = handle.progress();
+
     spawn_local(async move {
-        let status = execute_entity_fetch::<_, MV>(pending_fetch, context).await;
-        result_callback(status);
-        transfer_state.lock_mut().stop(status);
+        let (status, headers) = execute_entity_fetch::<_, MV>(pending_fetch, context).await;
+        if progress.complete() {
+            result_callback(status, headers);
+            transfer_state.lock_mut().stop(status);
+        }
     });
+
+    handle
 }
 
 async fn execute_entity_fetch<E, MV>(
     pending_fetch: PendingFetch,
+    context: EntityFetchContext<E>,
+) -> (StatusCode, ResponseHeaders)
+where
+    E: DeserializeOwned,
+    MV: MacVerify,
+{
+    let result = execute_fetch::<EntityResponse<E>, MV>(pending_fetch)
+        .await
+        .map_response(Ok::<_, Infallible>);
+    complete_entity_fetch(result, context, None)
+}
+
+fn complete_entity_fetch<E, R2>(
+    mut result: DecodedResponse<Result<EntityResponse<E>, R2>>,
     EntityFetchContext {
         logging,
+        empty_means_none,
         messages,
         storage_entity,
     }: EntityFetchContext<E>,
-) -> StatusCode
-where
-    E: DeserializeOwned,
-    MV: MacVerify,
-{
-    let mut result = execute_fetch::<EntityResponse<E>, MV>(pending_fetch).await;
-    match (result.status(), result.take_response()) {
+    error_body: Option<MutableOption<R2>>,
+) -> (StatusCode, ResponseHeaders) {
+    let status = match (result.status(), result.take_response()) {
         (status @ StatusCode::FetchTimeout, _) => {
             if logging {
                 // TODO: should this warning go also to Messages???
@@ -740,11 +1640,12 @@ where
             }
             status
         }
-        (status @ StatusCode::FetchFailed, _) => {
+        (status @ (StatusCode::FetchFailed | StatusCode::Aborted), _) => {
             if logging {
                 // TODO: should this warning go also to Messages???
                 debug!(
-                    "Request failed in execution, error: {}",
+                    "Request failed in execution ({}), error: {}",
+                    result.error().map(FetchError::name).unwrap_or("?unknown"),
                     result.hint().unwrap_or("?unknown")
                 );
             }
@@ -752,19 +1653,39 @@ where
         }
         (status @ StatusCode::DecodeFailed, _) => {
             if logging {
-                // TODO: should this warning go also to Messages???
                 warn!(
                     "Response decoding failed, error: {}",
                     result.hint().unwrap_or("?unknown")
                 );
             }
+            messages.replace(Messages::from_service_error(
+                "Request failed as response cannot be decoded",
+            ));
+            status
+        }
+        (status, None) => {
+            if empty_means_none
+                && status.is_success()
+                && !matches!(status, StatusCode::NoContent | StatusCode::NotModified)
+                && let Some(storage_entity) = storage_entity
+            {
+                storage_entity.set(None);
+            }
+            status
+        }
+        (status, Some(Err(response_error))) => {
+            messages.clear_all();
+            if let Some(error_body) = error_body {
+                error_body.set(Some(response_error));
+            }
             status
         }
-        (status, None) => status,
-        (status, Some(response)) => {
+        (status, Some(Ok(response))) => {
             let (received_entity, response_messages) = response.take();
             messages.replace(response_messages);
-            if let (Some(entity), Some(response_entity)) = (received_entity, storage_entity) {
+            if status != StatusCode::NotModified
+                && let (Some(entity), Some(response_entity)) = (received_entity, storage_entity)
+            {
                 if logging {
                     trace!("Request successfully loaded entity");
                 }
@@ -772,9 +1693,67 @@ where
             }
             status
         }
+    };
+    (status, result.take_headers())
+}
+
+impl<E, MV> EntityStore<E, MV> {
+    fn share(&self) -> Self {
+        Self {
+            transfer_state: self.transfer_state.clone(),
+            transfer_generation: self.transfer_generation.clone(),
+            messages: self.messages.clone(),
+            entity: self.entity.clone(),
+            etag: self.etag.clone(),
+            logging: self.logging,
+            pmv: PhantomData,
+        }
     }
 }
 
+// background activity of a store runs until dropped
+#[must_use]
+pub struct Subscription {
+    active: Rc<Cell<bool>>,
+}
+
+impl Subscription {
+    pub(super) fn new() -> Self {
+        Self {
+            active: Rc::new(Cell::new(true)),
+        }
+    }
+
+    // the activity checks the flag before each step
+    pub(super) fn active(&self) -> Rc<Cell<bool>> {
+        self.active.clone()
+    }
+
+    // ends `signal` with the first change after the subscription is dropped
+    pub(super) fn until_dropped<S: Signal>(
+        &self,
+        signal: S,
+    ) -> impl Signal<Item = S::Item> + use<S> {
+        let active = self.active();
+        signal.stop_if(move |_| !active.get())
+    }
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        self.active.set(false);
+    }
+}
+
+pub type AutosaveGuard = Subscription;
+pub type PollHandle = Subscription;
+pub type RetryHandle = Subscription;
+
+// `Retry-After` of the response takes precedence over the backoff
+fn retry_delay(retry_after: Option<Duration>, backoff: Duration, max: Duration) -> Duration {
+    retry_after.unwrap_or(backoff).min(max)
+}
+
 impl<E, MV> Default for EntityStore<E, MV> {
     fn default() -> Self {
         Self::new(None)
@@ -798,6 +1777,85 @@ impl<E, MV> From<&EntityStore<E, MV>> for Messages {
 
 struct EntityFetchContext<E> {
     logging: bool,
+    empty_means_none: bool,
     messages: Messages,
     storage_entity: Option<MutableOption<E>>,
 }
+
+#[cfg(test)]
+mod tests {
+    use std::task::{Context, Poll, Waker};
+
+    use super::*;
+
+    #[test]
+    fn delete_without_entity() {
+        let store = EntityStore::<u8>::new(None);
+        let request = store.delete_request(Request::new("/items/1"));
+        assert_eq!(&Method::Delete, request.method());
+        assert!(!request.is_load());
+        assert_eq!(None, request.body_size());
+    }
+
+    #[test]
+    fn subscription_drop_deactivates() {
+        let subscription = Subscription::new();
+        let active = subscription.active();
+        assert!(active.get());
+
+        drop(subscription);
+        assert!(!active.get());
+    }
+
+    #[test]
+    fn subscription_drop_ends_signal() {
+        let store = EntityStore::<u8>::default();
+        let subscription = Subscription::new();
+        let mut changes = Box::pin(subscription.until_dropped(store.transfer_state().signal()));
+        let mut poll = || {
+            changes
+                .as_mut()
+                .poll_change(&mut Context::from_waker(Waker::noop()))
+        };
+        assert_eq!(Poll::Ready(Some(TransferState::Empty)), poll());
+
+        drop(subscription);
+        store.set_transfer_state(TransferState::PendingLoad);
+        assert_eq!(Poll::Ready(Some(TransferState::PendingLoad)), poll());
+        assert_eq!(Poll::Ready(None), poll());
+    }
+
+    #[test]
+    fn retry_delay_is_capped() {
+        let max = Duration::from_secs(60);
+        let backoff = Duration::from_millis(500);
+        assert_eq!(backoff, retry_delay(None, backoff, max));
+        assert_eq!(
+            Duration::from_secs(2),
+            retry_delay(Some(Duration::from_secs(2)), backoff, max)
+        );
+        assert_eq!(max, retry_delay(Some(Duration::MAX), backoff, max));
+        assert_eq!(max, retry_delay(None, Duration::MAX.saturating_mul(2), max));
+    }
+
+    #[test]
+    fn poll_skips_pending_and_stops_on_drop() {
+        let store = EntityStore::<u8>::default();
+        let subscription = Subscription::new();
+        let active = subscription.active();
+        let loads = Cell::new(0);
+        let load = || loads.set(loads.get() + 1);
+
+        assert!(store.poll_tick(&active, load));
+        assert_eq!(1, loads.get());
+
+        store.set_transfer_state(TransferState::PendingLoad);
+        assert!(store.poll_tick(&active, load));
+        assert_eq!(1, loads.get());
+
+        store.set_transfer_state(TransferState::Empty);
+        drop(subscription);
+        assert!(!store.poll_tick(&active, load));
+        assert_eq!(1, loads.get());
+    }
+}
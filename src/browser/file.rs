@@ -1,6 +1,11 @@
+use js_sys::{Array, Uint8Array};
 use smol_str::SmolStr;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::FilePropertyBag;
 
-use crate::MediaType;
+use crate::{MediaType, uformat_smolstr};
+
+use super::js_error;
 
 #[derive(Clone)]
 pub struct File {
@@ -32,6 +37,68 @@ impl File {
     pub fn media_type(&self) -> MediaType {
         self.inner.type_().as_str().into()
     }
+
+    pub fn size(&self) -> u64 {
+        self.inner.size() as u64
+    }
+
+    // the slice is named `<name>.<start>-<end>`
+    pub fn slice(&self, start: u64, end: u64) -> Result<File, SmolStr> {
+        if start > end {
+            return Err(uformat_smolstr!("Invalid file slice {}-{}", start, end));
+        }
+
+        let blob = self
+            .inner
+            .slice_with_f64_and_f64(start as f64, end as f64)
+            .map_err(js_error)?;
+        let options = FilePropertyBag::new();
+        options.set_type(&self.inner.type_());
+        let name = uformat_smolstr!("{}.{}-{}", self.inner.name().as_str(), start, end);
+        web_sys::File::new_with_blob_sequence_and_options(&Array::of1(&blob), &name, &options)
+            .map(File::from)
+            .map_err(|error| js_error(error).into())
+    }
+
+    pub async fn read_bytes(&self) -> Result<Vec<u8>, SmolStr> {
+        let buffer = JsFuture::from(self.inner.array_buffer())
+            .await
+            .map_err(js_error)?;
+        Ok(Uint8Array::new(&buffer).to_vec())
+    }
+
+    // base64 digest as used by `integrity` attribute
+    #[cfg(feature = "sha256")]
+    pub async fn sha256(&self) -> Result<SmolStr, SmolStr> {
+        use sha2::{Digest, Sha256};
+
+        let bytes = self.read_bytes().await?;
+        Ok(digest_base64(Sha256::digest(bytes)))
+    }
+
+    // the file is never held in memory whole
+    #[cfg(feature = "sha256")]
+    pub async fn sha256_chunked(&self, chunk_size: u64) -> Result<SmolStr, SmolStr> {
+        use sha2::{Digest, Sha256};
+
+        let chunk_size = chunk_size.max(1);
+        let size = self.size();
+        let mut hasher = Sha256::new();
+        let mut start = 0;
+        while start < size {
+            let end = size.min(start + chunk_size);
+            hasher.update(self.slice(start, end)?.read_bytes().await?);
+            start = end;
+        }
+        Ok(digest_base64(hasher.finalize()))
+    }
+}
+
+#[cfg(feature = "sha256")]
+fn digest_base64(digest: impl AsRef<[u8]>) -> SmolStr {
+    use base64::{Engine, engine::general_purpose};
+
+    general_purpose::STANDARD.encode(digest).into()
 }
 
 #[derive(PartialEq, Eq)]
@@ -48,9 +115,29 @@ impl FileList {
         self.inner.length() as usize
     }
 
+    pub fn get(&self, index: usize) -> Option<File> {
+        u32::try_from(index)
+            .ok()
+            .and_then(|index| self.inner.get(index))
+            .map(File::from)
+    }
+
     pub fn iter(&self) -> FileListIterator<'_> {
         FileListIterator::new(self)
     }
+
+    pub fn to_vec(&self) -> Vec<File> {
+        self.iter().collect()
+    }
+}
+
+impl<'a> IntoIterator for &'a FileList {
+    type Item = File;
+    type IntoIter = FileListIterator<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
 }
 
 impl From<web_sys::FileList> for FileList {
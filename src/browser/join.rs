@@ -0,0 +1,112 @@
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+};
+
+use log::debug;
+
+use crate::StatusCode;
+
+use super::{request::Request, spawn_local};
+
+pub type JoinCallback = Box<dyn FnOnce(StatusCode)>;
+
+// statuses are passed to `on_complete` in the order of `requests`, bodies are not decoded
+pub fn join_all<C>(requests: Vec<(Request<'_>, JoinCallback)>, on_complete: C)
+where
+    C: FnOnce(Vec<StatusCode>) + 'static,
+{
+    if requests.is_empty() {
+        on_complete(vec![]);
+        return;
+    }
+
+    let join = Rc::new(Join::new(requests.len(), on_complete));
+    for (index, (request, result_callback)) in requests.into_iter().enumerate() {
+        let logging = request.logging();
+        if logging {
+            debug!("Request to {} joined", request.url());
+        }
+
+        let join = join.clone();
+        match request.start() {
+            Ok(pending_fetch) => {
+                spawn_local(async move {
+                    let result = pending_fetch.wait_completion().await;
+                    let status = result.status();
+                    if logging && let Some(hint) = result.hint() {
+                        debug!("Joined request completed with {status}, hint: {hint}");
+                    }
+                    join.settle(index, status, result_callback);
+                });
+            }
+            Err(error) => {
+                if logging {
+                    debug!("Request failed at init, error: {error}");
+                }
+                join.settle(index, StatusCode::BadRequest, result_callback);
+            }
+        }
+    }
+}
+
+struct Join<C> {
+    statuses: RefCell<Vec<StatusCode>>,
+    remaining: Cell<usize>,
+    on_complete: Cell<Option<C>>,
+}
+
+impl<C> Join<C>
+where
+    C: FnOnce(Vec<StatusCode>),
+{
+    fn new(count: usize, on_complete: C) -> Self {
+        Self {
+            statuses: RefCell::new(vec![StatusCode::Undefined; count]),
+            remaining: Cell::new(count),
+            on_complete: Cell::new(Some(on_complete)),
+        }
+    }
+
+    fn settle(&self, index: usize, status: StatusCode, result_callback: JoinCallback) {
+        result_callback(status);
+        self.statuses.borrow_mut()[index] = status;
+        self.remaining.set(self.remaining.get() - 1);
+        if self.remaining.get() == 0
+            && let Some(on_complete) = self.on_complete.take()
+        {
+            on_complete(self.statuses.take());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn completes_once_all_settled() {
+        let completed = Rc::new(RefCell::new(None));
+        let join = Join::new(2, {
+            let completed = completed.clone();
+            move |statuses| *completed.borrow_mut() = Some(statuses)
+        });
+
+        let settled = Rc::new(Cell::new(0));
+        let callback = || -> JoinCallback {
+            let settled = settled.clone();
+            Box::new(move |_| settled.set(settled.get() + 1))
+        };
+
+        join.settle(1, StatusCode::NotFound, callback());
+        assert_eq!(1, settled.get());
+        assert!(completed.borrow().is_none());
+
+        join.settle(0, StatusCode::Ok, callback());
+        assert_eq!(2, settled.get());
+        assert_eq!(
+            Some(vec![StatusCode::Ok, StatusCode::NotFound]),
+            completed.take()
+        );
+    }
+}
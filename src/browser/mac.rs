@@ -17,3 +17,114 @@ pub struct NoMac;
 
 impl MacSign for NoMac {}
 impl MacVerify for NoMac {}
+
+#[cfg(feature = "hmac")]
+pub use hmac_sha256::*;
+#[cfg(feature = "hmac")]
+mod hmac_sha256 {
+    use std::marker::PhantomData;
+
+    use base64::{Engine, engine::general_purpose};
+    use hmac::{Hmac, Mac};
+    use sha2::Sha256;
+    use smol_str::SmolStr;
+
+    use crate::uformat_smolstr;
+
+    use super::{MacSign, MacVerify};
+
+    pub trait HmacKey {
+        fn key() -> &'static [u8];
+    }
+
+    #[derive(Debug)]
+    pub struct HmacSha256<K>(PhantomData<K>);
+
+    impl<K> HmacSha256<K>
+    where
+        K: HmacKey,
+    {
+        fn mac(message: &[u8]) -> Hmac<Sha256> {
+            let mut mac =
+                Hmac::<Sha256>::new_from_slice(K::key()).expect("HMAC accepts key of any size");
+            mac.update(message);
+            mac
+        }
+    }
+
+    impl<K> MacSign for HmacSha256<K>
+    where
+        K: HmacKey,
+    {
+        fn sign(message: &[u8]) -> Option<SmolStr> {
+            let signature = Self::mac(message).finalize().into_bytes();
+            Some(general_purpose::STANDARD.encode(signature).into())
+        }
+    }
+
+    impl<K> MacVerify for HmacSha256<K>
+    where
+        K: HmacKey,
+    {
+        fn verify(message: &[u8], signature: Option<&str>) -> Result<bool, SmolStr> {
+            let Some(signature) = signature else {
+                return Ok(false);
+            };
+            let signature = general_purpose::STANDARD
+                .decode(signature)
+                .map_err(|e| uformat_smolstr!("Signature is not base64: {}", e.to_string()))?;
+            Ok(Self::mac(message).verify_slice(&signature).is_ok())
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        struct TestKey;
+
+        impl HmacKey for TestKey {
+            fn key() -> &'static [u8] {
+                b"test key"
+            }
+        }
+
+        struct OtherKey;
+
+        impl HmacKey for OtherKey {
+            fn key() -> &'static [u8] {
+                b"other key"
+            }
+        }
+
+        type TestMac = HmacSha256<TestKey>;
+
+        #[test]
+        fn signature_is_verified() {
+            let signature = TestMac::sign(b"message").unwrap();
+            assert_eq!(Ok(true), TestMac::verify(b"message", Some(&signature)));
+        }
+
+        #[test]
+        fn modified_message_is_rejected() {
+            let signature = TestMac::sign(b"message").unwrap();
+            assert_eq!(Ok(false), TestMac::verify(b"massage", Some(&signature)));
+        }
+
+        #[test]
+        fn other_key_is_rejected() {
+            let signature = HmacSha256::<OtherKey>::sign(b"message").unwrap();
+            assert_eq!(Ok(false), TestMac::verify(b"message", Some(&signature)));
+        }
+
+        #[test]
+        fn missing_signature_is_rejected() {
+            assert_eq!(Ok(false), TestMac::verify(b"message", None));
+        }
+
+        #[test]
+        fn malformed_signature_fails() {
+            assert!(TestMac::verify(b"message", Some("not base64!")).is_err());
+        }
+    }
+}
@@ -0,0 +1,586 @@
+use std::{
+    cell::{Cell, RefCell},
+    future::{Future, poll_fn},
+    io::Read,
+    pin::Pin,
+    rc::Rc,
+    task::{Poll, Waker},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use artwrap::{TimeoutFutureExt, spawn_blocking};
+use async_executor::LocalExecutor;
+use smol_str::{SmolStr, ToSmolStr};
+
+use crate::{StatusCode, uformat_smolstr};
+
+use super::{
+    FetchError,
+    common::{DecodedResponse, DeserializeMode, ResponseHeaders},
+};
+
+thread_local! {
+    static EXECUTOR: LocalExecutor<'static> = const { LocalExecutor::new() };
+}
+
+// tasks of stores run on the executor of the current thread, driven by `block_on`
+pub(crate) fn spawn_local<F>(future: F)
+where
+    F: Future + 'static,
+{
+    EXECUTOR.with(|executor| executor.spawn(future).detach());
+}
+
+// runs `future` together with fetches and other tasks started by stores of the current thread
+pub fn block_on<T>(future: impl Future<Output = T>) -> T {
+    EXECUTOR.with(|executor| async_io::block_on(executor.run(future)))
+}
+
+#[derive(Clone)]
+pub struct Abort {
+    state: Rc<AbortState>,
+}
+
+#[derive(Default)]
+struct AbortState {
+    aborted: Cell<bool>,
+    wakers: RefCell<Vec<Waker>>,
+}
+
+impl Abort {
+    pub fn new() -> Result<Self, SmolStr> {
+        Ok(Self {
+            state: Rc::default(),
+        })
+    }
+
+    pub fn abort(&self) {
+        if !self.state.aborted.replace(true) {
+            for waker in self.state.wakers.take() {
+                waker.wake();
+            }
+        }
+    }
+
+    pub fn is_aborted(&self) -> bool {
+        self.state.aborted.get()
+    }
+
+    fn register(&self, waker: &Waker) {
+        let mut wakers = self.state.wakers.borrow_mut();
+        if !wakers.iter().any(|registered| registered.will_wake(waker)) {
+            wakers.push(waker.clone());
+        }
+    }
+}
+
+pub(crate) struct Response {
+    status: u16,
+    url: SmolStr,
+    headers: ResponseHeaders,
+    body: Vec<u8>,
+}
+
+type RequestFuture = Pin<Box<dyn Future<Output = Result<Response, FetchError>>>>;
+
+pub(crate) struct PendingFetch {
+    url: SmolStr,
+    abort: Abort,
+    timeout: Option<Duration>,
+    request_future: RequestFuture,
+    external_abort: Option<Abort>,
+    deserialize_mode: DeserializeMode,
+}
+
+impl PendingFetch {
+    pub fn new(
+        url: impl ToSmolStr,
+        abort: Abort,
+        timeout: Option<Duration>,
+        request_future: impl Future<Output = Result<Response, FetchError>> + 'static,
+    ) -> Self {
+        Self {
+            url: url.to_smolstr(),
+            abort,
+            timeout,
+            request_future: Box::pin(request_future),
+            external_abort: None,
+            deserialize_mode: DeserializeMode::Deserialize,
+        }
+    }
+
+    // the external abort aborts the request, the request itself aborts only its own
+    pub fn with_external_abort(mut self, external_abort: Option<Abort>) -> Self {
+        self.external_abort = external_abort;
+        self
+    }
+
+    pub fn with_deserialize_mode(mut self, deserialize_mode: DeserializeMode) -> Self {
+        self.deserialize_mode = deserialize_mode;
+        self
+    }
+
+    pub fn abort(&self) -> &Abort {
+        &self.abort
+    }
+
+    pub fn deserialize_mode(&self) -> DeserializeMode {
+        self.deserialize_mode
+    }
+
+    pub async fn wait_completion(self) -> DecodedResponse<Response> {
+        let Self {
+            url,
+            abort,
+            timeout,
+            mut request_future,
+            external_abort,
+            ..
+        } = self;
+
+        // aborted request keeps running on its thread, its response is dropped
+        let completion = poll_fn(|cx| {
+            if external_abort.as_ref().is_some_and(Abort::is_aborted) {
+                abort.abort();
+            }
+            if abort.is_aborted() {
+                return Poll::Ready(None);
+            }
+            match request_future.as_mut().poll(cx) {
+                Poll::Ready(response) => Poll::Ready(Some(response)),
+                Poll::Pending => {
+                    abort.register(cx.waker());
+                    if let Some(external_abort) = &external_abort {
+                        external_abort.register(cx.waker());
+                    }
+                    Poll::Pending
+                }
+            }
+        });
+        let response = match timeout {
+            Some(timeout) => completion.timeout(timeout).await,
+            None => Ok(completion.await),
+        };
+        match response {
+            Ok(Some(Ok(mut response))) => DecodedResponse::new(StatusCode::from(response.status))
+                .with_headers(std::mem::take(&mut response.headers))
+                .with_final_url(response.url.clone())
+                .with_response(response),
+            Ok(Some(Err(error))) => DecodedResponse::new(StatusCode::FetchFailed)
+                .with_hint(uformat_smolstr!(
+                    "Fetch start failed ({})",
+                    error.to_string().as_str()
+                ))
+                .with_error(error),
+            Ok(None) => DecodedResponse::new(StatusCode::Aborted).with_hint("Fetch aborted"),
+            Err(_) => {
+                abort.abort();
+                DecodedResponse::new(StatusCode::FetchTimeout).with_hint(url)
+            }
+        }
+    }
+}
+
+// ureq blocks, so the request is sent from the blocking thread pool
+pub(crate) fn send(
+    verb: &'static str,
+    url: SmolStr,
+    headers: Vec<(&'static str, SmolStr)>,
+    body: Option<Vec<u8>>,
+    timeout: Option<Duration>,
+) -> impl Future<Output = Result<Response, FetchError>> {
+    spawn_blocking(move || {
+        let mut request = ureq::request(verb, &url);
+        for (name, value) in &headers {
+            request = request.set(name, value);
+        }
+        if let Some(timeout) = timeout {
+            request = request.timeout(timeout);
+        }
+
+        let response = match body {
+            Some(body) => request.send_bytes(&body),
+            None => request.call(),
+        };
+        let response = match response {
+            Ok(response) | Err(ureq::Error::Status(_, response)) => response,
+            Err(ureq::Error::Transport(transport)) => {
+                return Err(FetchError {
+                    name: transport.kind().to_smolstr(),
+                    message: transport.message().unwrap_or_default().into(),
+                });
+            }
+        };
+
+        let status = response.status();
+        let url = response.get_url().to_smolstr();
+        let headers = response
+            .headers_names()
+            .into_iter()
+            .filter_map(|name| {
+                let value = response.header(&name)?.to_smolstr();
+                Some((name, value))
+            })
+            .collect();
+        let mut body = Vec::new();
+        response
+            .into_reader()
+            .read_to_end(&mut body)
+            .map_err(|error| FetchError {
+                name: SmolStr::new_static("ReadError"),
+                message: error.to_smolstr(),
+            })?;
+        Ok(Response {
+            status,
+            url,
+            headers,
+            body,
+        })
+    })
+}
+
+pub(crate) fn now_seconds() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |now| now.as_secs())
+}
+
+pub(crate) async fn response_bytes(response: Response) -> Result<Vec<u8>, (StatusCode, SmolStr)> {
+    Ok(response.body)
+}
+
+// the body is read whole by the blocking request, so it makes a single chunk
+pub(crate) struct BodyReader {
+    body: Option<Vec<u8>>,
+}
+
+impl BodyReader {
+    pub(crate) fn new(response: Response) -> Self {
+        Self {
+            body: Some(response.body).filter(|body| !body.is_empty()),
+        }
+    }
+
+    pub(crate) async fn next_chunk(&mut self) -> Result<Option<Vec<u8>>, SmolStr> {
+        Ok(self.body.take())
+    }
+}
+
+#[cfg(all(test, feature = "json"))]
+mod tests {
+    use std::{
+        cell::{Cell, RefCell},
+        io::{BufRead, BufReader, Write},
+        net::TcpListener,
+        sync::{Arc, Mutex},
+        thread,
+    };
+
+    use artwrap::sleep;
+    use futures_signals::signal::Mutable;
+
+    use super::*;
+    use crate::{
+        Dirty, NoMac, Paging,
+        browser::{BatchStore, CollectionStore, EntityStore, Request, TransferState},
+    };
+
+    struct TestServer {
+        url: String,
+        requests: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl TestServer {
+        fn requests(&self) -> Vec<String> {
+            self.requests.lock().unwrap().clone()
+        }
+    }
+
+    // answers every request with `body`, keeping the request lines
+    fn serve(content_type: &'static str, body: &'static str) -> TestServer {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        thread::spawn({
+            let requests = requests.clone();
+            move || {
+                for stream in listener.incoming().flatten() {
+                    let mut reader = BufReader::new(&stream);
+                    let mut line = String::new();
+                    let _ = reader.read_line(&mut line);
+                    requests.lock().unwrap().push(line.trim_end().to_owned());
+                    while reader.read_line(&mut line).is_ok_and(|read| read > 2) {
+                        line.clear();
+                    }
+                    let _ = write!(
+                        &stream,
+                        "HTTP/1.1 200 OK\r\nContent-Type: {content_type}\r\n\
+                         Content-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                        body.len()
+                    );
+                }
+            }
+        });
+        TestServer { url, requests }
+    }
+
+    #[test]
+    fn entity_store_loads() {
+        let server = serve("application/json", r#"{"messages":{},"entity":7}"#);
+        let store = EntityStore::<u8>::new(None);
+        let status = Rc::new(Cell::new(None));
+
+        block_on(async {
+            store
+                .load(Request::new("value").with_base(&server.url).json(), {
+                    let status = status.clone();
+                    move |result| status.set(Some(result))
+                })
+                .detach();
+            while status.get().is_none() {
+                sleep(Duration::from_millis(5)).await;
+            }
+        });
+
+        assert_eq!(Some(StatusCode::Ok), status.get());
+        assert_eq!(Some(7), store.get());
+        assert_eq!(1, server.requests().len());
+    }
+
+    fn load_ndjson(server: &TestServer, store: &CollectionStore<u8>) -> Option<StatusCode> {
+        let status = Rc::new(Cell::new(None));
+        block_on(async {
+            store.load_ndjson(Request::new("items").with_base(&server.url), {
+                let status = status.clone();
+                move |result| status.set(Some(result))
+            });
+            while status.get().is_none() {
+                sleep(Duration::from_millis(5)).await;
+            }
+        });
+        status.get()
+    }
+
+    #[test]
+    fn ndjson_failure_restores_collection() {
+        let store = CollectionStore::<u8>::new_value(vec![7]);
+
+        let server = serve("application/x-ndjson", "3\n1\nx\n");
+        assert_eq!(Some(StatusCode::DecodeFailed), load_ndjson(&server, &store));
+        assert_eq!(vec![7], store.get_cloned());
+
+        let server = serve("application/json", "[3, 1]");
+        assert_eq!(
+            Some(StatusCode::UnsupportedMediaType),
+            load_ndjson(&server, &store)
+        );
+        assert_eq!(vec![7], store.get_cloned());
+
+        let server = serve("application/x-ndjson; charset=utf-8", "3\n1\n");
+        assert_eq!(Some(StatusCode::Ok), load_ndjson(&server, &store));
+        assert_eq!(vec![3, 1], store.get_cloned());
+    }
+
+    #[test]
+    fn next_page_keeps_request() {
+        let server = serve(
+            "application/json",
+            r#"{"messages":{},"paging":{"limit":25},"collection":[2]}"#,
+        );
+        let store = CollectionStore::<u8>::new_value(vec![1]);
+        store
+            .paging()
+            .set(Paging::default().with_next(Some("p2".into())));
+        let status = Rc::new(Cell::new(None));
+
+        block_on(async {
+            let started = store.load_next_page(
+                Request::new("items")
+                    .with_base(&server.url)
+                    .with_query_param("filter", "new")
+                    .json(),
+                {
+                    let status = status.clone();
+                    move |result| status.set(Some(result))
+                },
+            );
+            assert!(started);
+            while status.get().is_none() {
+                sleep(Duration::from_millis(5)).await;
+            }
+        });
+
+        assert_eq!(Some(StatusCode::Ok), status.get());
+        assert_eq!(
+            vec!["GET /items?filter=new&cursor=p2 HTTP/1.1"],
+            server.requests()
+        );
+        assert_eq!(vec![1, 2], store.get_cloned());
+    }
+
+    #[test]
+    fn bind_url_continues_after_refused_load() {
+        let server = serve(
+            "application/json",
+            r#"{"messages":{},"paging":{"limit":25},"collection":[2]}"#,
+        );
+        let store = CollectionStore::<u8>::new();
+        store.set_transfer_state(TransferState::PendingLoad);
+        let url = Mutable::new(SmolStr::new_static("a"));
+        let statuses = Rc::new(RefCell::new(Vec::new()));
+
+        block_on(async {
+            let base = server.url.clone();
+            let _binding = store.bind_url(
+                url.signal_cloned(),
+                move |url| {
+                    Request::new(url)
+                        .with_base(&base)
+                        .with_dedupe_inflight(true)
+                        .json()
+                },
+                {
+                    let statuses = statuses.clone();
+                    move |status| statuses.borrow_mut().push(status)
+                },
+            );
+            sleep(Duration::from_millis(20)).await;
+            store.set_transfer_state(TransferState::Empty);
+            url.set(SmolStr::new_static("b"));
+            while statuses.borrow().is_empty() {
+                sleep(Duration::from_millis(5)).await;
+            }
+        });
+
+        assert_eq!(vec![StatusCode::Ok], *statuses.borrow());
+        assert_eq!(vec!["GET /b HTTP/1.1"], server.requests());
+        assert_eq!(vec![2], store.get_cloned());
+    }
+
+    #[test]
+    fn abort_completes_as_aborted() {
+        let abort = Abort::new().unwrap();
+        let fetch = PendingFetch::new("/never", abort.clone(), None, std::future::pending());
+        abort.abort();
+
+        let response = block_on(fetch.wait_completion());
+        assert_eq!(StatusCode::Aborted, response.status());
+    }
+
+    #[test]
+    fn timeout_keeps_shared_abort() {
+        let shared = Abort::new().unwrap();
+        let pending = |timeout| {
+            PendingFetch::new(
+                "/never",
+                Abort::new().unwrap(),
+                timeout,
+                std::future::pending(),
+            )
+            .with_external_abort(Some(shared.clone()))
+        };
+        let timed_out = pending(Some(Duration::from_millis(10)));
+        let sibling = pending(None);
+
+        let response = block_on(timed_out.wait_completion());
+        assert_eq!(StatusCode::FetchTimeout, response.status());
+        assert!(!shared.is_aborted());
+
+        shared.abort();
+        let response = block_on(sibling.wait_completion());
+        assert_eq!(StatusCode::Aborted, response.status());
+    }
+
+    #[test]
+    fn poll_handle_stops_loads_on_drop() {
+        let server = serve("application/json", r#"{"messages":{},"entity":7}"#);
+        let store = EntityStore::<u8>::new(None);
+
+        block_on(async {
+            let handle = store.poll(
+                {
+                    let url = server.url.clone();
+                    move || Request::new("value").with_base(&url).json()
+                },
+                Duration::from_millis(20),
+                |_| (),
+            );
+            sleep(Duration::from_millis(150)).await;
+            drop(handle);
+            while store.pending() {
+                sleep(Duration::from_millis(5)).await;
+            }
+
+            let loads = server.requests().len();
+            assert!(loads > 0);
+            sleep(Duration::from_millis(100)).await;
+            assert_eq!(loads, server.requests().len());
+        });
+        assert_eq!(Some(7), store.get());
+    }
+
+    #[derive(Clone, serde::Serialize, serde::Deserialize)]
+    struct Draft {
+        value: u8,
+        #[serde(skip)]
+        dirty: bool,
+    }
+
+    impl Dirty for Draft {
+        fn is_dirty(&self) -> bool {
+            self.dirty
+        }
+
+        fn take_dirty(&mut self) -> bool {
+            std::mem::take(&mut self.dirty)
+        }
+
+        fn with_dirty(mut self) -> Self {
+            self.dirty = true;
+            self
+        }
+
+        fn mark_as_dirty(&mut self) {
+            self.dirty = true;
+        }
+    }
+
+    #[test]
+    fn batch_store_marks_entities_stored() {
+        let server = serve("application/json", r#"{"messages":{}}"#);
+        let store = EntityStore::<Draft>::new(Some(Draft {
+            value: 7,
+            dirty: true,
+        }));
+        let batch = BatchStore::<NoMac>::new().with_entity("draft", &store);
+        let status = Rc::new(Cell::new(None));
+
+        store.transfer_state().set(TransferState::PendingLoad);
+        let request = || Request::new("batch").with_base(&server.url).create().json();
+        batch
+            .store::<NoMac, _>(request(), {
+                let status = status.clone();
+                move |result| status.set(Some(result))
+            })
+            .detach();
+        assert_eq!(Some(StatusCode::Undefined), status.take());
+        assert!(server.requests().is_empty());
+
+        store.transfer_state().set(TransferState::Empty);
+        block_on(async {
+            batch
+                .store::<NoMac, _>(request(), {
+                    let status = status.clone();
+                    move |result| status.set(Some(result))
+                })
+                .detach();
+            while status.get().is_none() {
+                sleep(Duration::from_millis(5)).await;
+            }
+        });
+
+        assert_eq!(Some(StatusCode::Ok), status.get());
+        assert_eq!(Some(StatusCode::Ok), store.stored_status());
+        assert!(!store.dirty());
+    }
+}
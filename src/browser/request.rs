@@ -1,25 +1,54 @@
-use std::time::Duration;
+#[cfg(feature = "browser")]
+use std::rc::Rc;
+use std::{
+    cell::{Cell, RefCell},
+    str::FromStr,
+    time::Duration,
+};
 
-use js_sys::Uint8Array;
+use base64::{Engine, engine::general_purpose};
 use log::warn;
-use smol_str::{SmolStr, ToSmolStr};
-use wasm_bindgen::JsValue;
+use smol_str::{SmolStr, SmolStrBuilder, ToSmolStr};
+
+#[cfg(feature = "browser")]
+use js_sys::{Function, Object, Promise, Reflect, Uint8Array};
+#[cfg(feature = "browser")]
+use wasm_bindgen::{JsCast, JsValue, closure::Closure};
+#[cfg(feature = "browser")]
 use wasm_bindgen_futures::JsFuture;
-use web_sys::{Headers, RequestInit};
+#[cfg(feature = "browser")]
+use web_sys::{
+    AbortSignal, Headers, ProgressEvent, ReadableStream, RequestInit, Response, ResponseInit,
+    XmlHttpRequest, XmlHttpRequestResponseType,
+};
+#[cfg(feature = "browser")]
+pub use web_sys::{RequestCache, RequestCredentials, RequestMode, RequestRedirect};
 
-use crate::{HEADER_ACCEPT, HEADER_CONTENT_TYPE, HEADER_WANTS_RESPONSE, MediaType};
+#[cfg(feature = "postcard")]
+use serde::Serialize;
 
-use super::{
-    common::{Abort, PendingFetch},
-    file::File,
-    js_error,
+#[cfg(feature = "json")]
+use crate::JSONSerialize;
+#[cfg(feature = "postcard")]
+use crate::PostcardSerialize;
+use crate::{
+    HEADER_ACCEPT, HEADER_AUTHORIZATION, HEADER_CONTENT_TYPE, HEADER_WANTS_RESPONSE, MediaType,
+    uformat_smolstr,
 };
 
+use super::common::{Abort, DeserializeMode, PendingFetch};
+#[cfg(not(feature = "browser"))]
+use super::native;
+#[cfg(feature = "browser")]
+use super::{file::File, js_error};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Method {
     Head,
     Get,
     Post,
     Put,
+    Patch,
     Delete,
     Options,
 }
@@ -31,6 +60,7 @@ impl Method {
             Self::Get => "Get",
             Self::Post => "Post",
             Self::Put => "Put",
+            Self::Patch => "Patch",
             Self::Delete => "Delete",
             Self::Options => "Options",
         }
@@ -39,37 +69,173 @@ impl Method {
     pub fn is_load(&self) -> bool {
         matches!(self, Self::Head | Self::Get | Self::Options)
     }
+
+    fn verb(&self) -> &'static str {
+        match self {
+            Self::Head => "HEAD",
+            Self::Get => "GET",
+            Self::Post => "POST",
+            Self::Put => "PUT",
+            Self::Patch => "PATCH",
+            Self::Delete => "DELETE",
+            Self::Options => "OPTIONS",
+        }
+    }
+}
+
+impl FromStr for Method {
+    type Err = SmolStr;
+
+    fn from_str(verb: &str) -> Result<Self, Self::Err> {
+        [
+            Self::Head,
+            Self::Get,
+            Self::Post,
+            Self::Put,
+            Self::Patch,
+            Self::Delete,
+            Self::Options,
+        ]
+        .into_iter()
+        .find(|method| method.verb().eq_ignore_ascii_case(verb))
+        .ok_or_else(|| uformat_smolstr!("Unknown method: {}", verb))
+    }
+}
+
+impl TryFrom<&str> for Method {
+    type Error = SmolStr;
+
+    fn try_from(verb: &str) -> Result<Self, Self::Error> {
+        verb.parse()
+    }
+}
+
+thread_local! {
+    static DEFAULT_TIMEOUT: Cell<Option<Duration>> = const { Cell::new(None) };
+    static DEFAULT_BASE_URL: RefCell<Option<SmolStr>> = const { RefCell::new(None) };
+}
+
+pub fn set_default_request_timeout(timeout: Duration) {
+    DEFAULT_TIMEOUT.set(Some(timeout));
+}
+
+pub fn default_request_timeout() -> Duration {
+    DEFAULT_TIMEOUT.get().unwrap_or(Request::DEFAULT_TIMEOUT)
+}
+
+// applies to requests created afterwards
+pub fn set_default_base_url(base: impl ToSmolStr) {
+    DEFAULT_BASE_URL.replace(Some(base.to_smolstr()));
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Timeout {
+    #[default]
+    Default,
+    None,
+    After(Duration),
+}
+
+impl Timeout {
+    pub fn duration(&self) -> Option<Duration> {
+        match self {
+            Self::Default => Some(default_request_timeout()),
+            Self::None => None,
+            Self::After(timeout) => Some(*timeout),
+        }
+    }
+}
+
+impl From<Duration> for Timeout {
+    fn from(timeout: Duration) -> Self {
+        Self::After(timeout)
+    }
 }
 
+impl From<Option<Duration>> for Timeout {
+    fn from(timeout: Option<Duration>) -> Self {
+        timeout.map_or(Self::None, Self::After)
+    }
+}
+
+#[cfg(feature = "browser")]
+type InitHook = Rc<dyn Fn(&RequestInit)>;
+
+// cloned request shares the `Abort` given by `with_abort`
+#[derive(Clone)]
 pub struct Request<'a> {
     logging: bool,
     method: Method,
     is_load: bool,
     url: &'a str,
+    base: Option<SmolStr>,
+    query: Option<Vec<(SmolStr, SmolStr)>>,
     headers: Option<Vec<(&'static str, SmolStr)>>,
     media_type: Option<MediaType>,
     body: Option<Body>,
+    body_error: Option<SmolStr>,
     wants_response: bool,
-    timeout: Option<Duration>,
+    timeout: Timeout,
+    abort: Option<Abort>,
+    #[cfg(feature = "browser")]
+    abort_signal: Option<AbortSignal>,
+    dedupe_inflight: bool,
+    empty_means_none: bool,
+    base64_body: bool,
+    #[cfg(feature = "browser")]
+    credentials: Option<RequestCredentials>,
+    #[cfg(feature = "browser")]
+    mode: Option<RequestMode>,
+    #[cfg(feature = "browser")]
+    cache: Option<RequestCache>,
+    #[cfg(feature = "browser")]
+    redirect: Option<RequestRedirect>,
+    #[cfg(feature = "browser")]
+    init_hook: Option<InitHook>,
 }
 
+#[derive(Clone)]
 enum Body {
     Bytes(Vec<u8>),
+    #[cfg(feature = "browser")]
     File(File),
+    #[cfg(feature = "browser")]
+    Stream(ReadableStream),
 }
 
 impl<'a> Request<'a> {
+    pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+
     pub fn new(url: &'a str) -> Self {
         Self {
             logging: true,
             method: Method::Get,
             is_load: true,
             url,
+            base: DEFAULT_BASE_URL.with_borrow(Clone::clone),
+            query: None,
             headers: None,
             media_type: None,
             body: None,
+            body_error: None,
             wants_response: false,
-            timeout: Some(Duration::from_secs(5)),
+            timeout: Timeout::Default,
+            abort: None,
+            #[cfg(feature = "browser")]
+            abort_signal: None,
+            dedupe_inflight: false,
+            empty_means_none: false,
+            base64_body: false,
+            #[cfg(feature = "browser")]
+            credentials: None,
+            #[cfg(feature = "browser")]
+            mode: None,
+            #[cfg(feature = "browser")]
+            cache: None,
+            #[cfg(feature = "browser")]
+            redirect: None,
+            #[cfg(feature = "browser")]
+            init_hook: None,
         }
     }
 
@@ -85,6 +251,26 @@ impl<'a> Request<'a> {
         self
     }
 
+    #[must_use]
+    pub fn with_query_param(mut self, key: &str, value: impl ToSmolStr) -> Self {
+        self.query
+            .get_or_insert_with(Vec::new)
+            .push((key.to_smolstr(), value.to_smolstr()));
+        self
+    }
+
+    #[must_use]
+    pub fn with_query_params<'k>(
+        mut self,
+        params: impl IntoIterator<Item = (&'k str, SmolStr)>,
+    ) -> Self {
+        let query = self.query.get_or_insert_with(Vec::new);
+        for (key, value) in params {
+            query.push((key.to_smolstr(), value));
+        }
+        self
+    }
+
     #[must_use]
     pub fn with_header(mut self, name: &'static str, value: impl ToSmolStr) -> Self {
         let mut headers = self.headers.take().unwrap_or_default();
@@ -94,6 +280,37 @@ impl<'a> Request<'a> {
         self
     }
 
+    // header names are matched regardless of case
+    #[must_use]
+    pub fn set_header(self, name: &'static str, value: impl ToSmolStr) -> Self {
+        self.without_header(name).with_header(name, value)
+    }
+
+    #[must_use]
+    pub fn without_header(mut self, name: &str) -> Self {
+        if let Some(headers) = self.headers.as_mut() {
+            headers.retain(|(header, _)| !header.eq_ignore_ascii_case(name));
+        }
+        self
+    }
+
+    #[must_use]
+    pub fn with_bearer_token(self, token: impl ToSmolStr) -> Self {
+        self.with_header(
+            HEADER_AUTHORIZATION,
+            SmolStr::from_iter(["Bearer ", token.to_smolstr().as_str()]),
+        )
+    }
+
+    #[must_use]
+    pub fn with_basic_auth(self, user: &str, password: &str) -> Self {
+        let credentials = general_purpose::STANDARD.encode([user, ":", password].concat());
+        self.with_header(
+            HEADER_AUTHORIZATION,
+            SmolStr::from_iter(["Basic ", credentials.as_str()]),
+        )
+    }
+
     #[must_use]
     pub fn with_headers(mut self, headers: Option<Vec<(&'static str, SmolStr)>>) -> Self {
         if let Some(new_headers) = headers {
@@ -119,12 +336,74 @@ impl<'a> Request<'a> {
         self
     }
 
+    #[cfg(feature = "browser")]
     #[must_use]
     pub fn with_file(mut self, file: File) -> Self {
         self.body = Some(Body::File(file));
         self
     }
 
+    #[cfg(feature = "browser")]
+    // needs HTTP/2, not in Firefox and Safari, cannot be retried nor report upload progress
+    #[must_use]
+    pub fn with_stream_body(mut self, stream: ReadableStream) -> Self {
+        self.body = Some(Body::Stream(stream));
+        self
+    }
+
+    #[must_use]
+    pub fn with_form_urlencoded<'k>(
+        self,
+        pairs: impl IntoIterator<Item = (&'k str, SmolStr)>,
+    ) -> Self {
+        let mut builder = SmolStrBuilder::new();
+        for (index, (key, value)) in pairs.into_iter().enumerate() {
+            if index > 0 {
+                builder.push('&');
+            }
+            encode_form_component(&mut builder, key);
+            builder.push('=');
+            encode_form_component(&mut builder, &value);
+        }
+        self.with_media_type(MediaType::Form)
+            .with_body(builder.finish().as_bytes().to_vec())
+    }
+
+    // serialization error fails the request at its start
+    #[cfg(feature = "json")]
+    #[must_use]
+    pub fn with_json_value(self, value: &serde_json::Value) -> Self {
+        self.with_serialized(MediaType::Json, value.to_json())
+    }
+
+    // serialization error fails the request at its start
+    #[cfg(feature = "postcard")]
+    #[must_use]
+    pub fn with_postcard_value(self, value: &impl Serialize) -> Self {
+        self.with_serialized(MediaType::Postcard, value.to_postcard())
+    }
+
+    #[cfg(any(feature = "json", feature = "postcard"))]
+    fn with_serialized(mut self, media_type: MediaType, body: Result<Vec<u8>, SmolStr>) -> Self {
+        match body {
+            Ok(body) => {
+                self.body_error = None;
+                self.with_media_type(media_type).with_body(body)
+            }
+            Err(error) => {
+                self.body_error = Some(error);
+                self
+            }
+        }
+    }
+
+    // absolute URL or URL starting with `/` is kept as is
+    #[must_use]
+    pub fn with_base(mut self, base: &str) -> Self {
+        self.base = Some(base.to_smolstr());
+        self
+    }
+
     #[must_use]
     pub fn with_is_load(mut self, is_load: bool) -> Self {
         self.is_load = is_load;
@@ -132,8 +411,80 @@ impl<'a> Request<'a> {
     }
 
     #[must_use]
-    pub fn with_timeout(mut self, timeout: Option<Duration>) -> Self {
-        self.timeout = timeout;
+    pub fn with_timeout(mut self, timeout: impl Into<Timeout>) -> Self {
+        self.timeout = timeout.into();
+        self
+    }
+
+    // aborting `abort` cancels all requests sharing it, a timeout cancels only its own request
+    #[must_use]
+    pub fn with_abort(mut self, abort: Abort) -> Self {
+        self.abort = Some(abort);
+        self
+    }
+
+    #[cfg(feature = "browser")]
+    // such request completes with `StatusCode::Aborted`
+    #[must_use]
+    pub fn with_abort_signal(mut self, signal: AbortSignal) -> Self {
+        self.abort_signal = Some(signal);
+        self
+    }
+
+    #[must_use]
+    pub fn with_dedupe_inflight(mut self, dedupe_inflight: bool) -> Self {
+        self.dedupe_inflight = dedupe_inflight;
+        self
+    }
+
+    // empty body clears the entity, which stays empty until invalidated
+    #[must_use]
+    pub fn with_empty_means_none(mut self, empty_means_none: bool) -> Self {
+        self.empty_means_none = empty_means_none;
+        self
+    }
+
+    // e.g. postcard passed through a text-only gateway
+    #[must_use]
+    pub fn with_base64_body(mut self, base64_body: bool) -> Self {
+        self.base64_body = base64_body;
+        self
+    }
+
+    #[cfg(feature = "browser")]
+    #[must_use]
+    pub fn with_credentials(mut self, credentials: RequestCredentials) -> Self {
+        self.credentials = Some(credentials);
+        self
+    }
+
+    #[cfg(feature = "browser")]
+    #[must_use]
+    pub fn with_mode(mut self, mode: RequestMode) -> Self {
+        self.mode = Some(mode);
+        self
+    }
+
+    #[cfg(feature = "browser")]
+    #[must_use]
+    pub fn with_cache(mut self, cache: RequestCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    #[cfg(feature = "browser")]
+    // `Manual` completes with `StatusCode::Found` regardless of the actual code
+    #[must_use]
+    pub fn with_redirect(mut self, redirect: RequestRedirect) -> Self {
+        self.redirect = Some(redirect);
+        self
+    }
+
+    #[cfg(feature = "browser")]
+    // runs last on every start, not at all for uploads reporting progress
+    #[must_use]
+    pub fn with_init_hook(mut self, hook: impl Fn(&RequestInit) + 'static) -> Self {
+        self.init_hook = Some(Rc::new(hook));
         self
     }
 
@@ -141,8 +492,12 @@ impl<'a> Request<'a> {
     pub fn encoding(mut self, media_type: impl Into<MediaType>) -> Self {
         let media_type = media_type.into();
         let media_type = match media_type {
+            #[cfg(feature = "cbor")]
+            MediaType::Cbor => MediaType::Cbor,
             #[cfg(feature = "json")]
             MediaType::Json => MediaType::Json,
+            #[cfg(feature = "messagepack")]
+            MediaType::MsgPack => MediaType::MsgPack,
             #[cfg(feature = "postcard")]
             MediaType::Postcard => MediaType::Postcard,
             _ => {
@@ -161,8 +516,12 @@ impl<'a> Request<'a> {
     pub fn encoding_with_response(mut self, media_type: impl Into<MediaType>) -> Self {
         let media_type = media_type.into();
         let media_type = match media_type {
+            #[cfg(feature = "cbor")]
+            MediaType::Cbor => MediaType::Cbor,
             #[cfg(feature = "json")]
             MediaType::Json => MediaType::Json,
+            #[cfg(feature = "messagepack")]
+            MediaType::MsgPack => MediaType::MsgPack,
             #[cfg(feature = "postcard")]
             MediaType::Postcard => MediaType::Postcard,
             _ => {
@@ -178,6 +537,20 @@ impl<'a> Request<'a> {
             .with_header(HEADER_WANTS_RESPONSE, "1")
     }
 
+    #[cfg(feature = "cbor")]
+    #[inline]
+    #[must_use]
+    pub fn cbor(self) -> Self {
+        self.encoding(MediaType::Cbor)
+    }
+
+    #[cfg(feature = "cbor")]
+    #[inline]
+    #[must_use]
+    pub fn cbor_with_response(self) -> Self {
+        self.encoding_with_response(MediaType::Cbor)
+    }
+
     #[cfg(feature = "json")]
     #[inline]
     #[must_use]
@@ -192,6 +565,20 @@ impl<'a> Request<'a> {
         self.encoding_with_response(MediaType::Json)
     }
 
+    #[cfg(feature = "messagepack")]
+    #[inline]
+    #[must_use]
+    pub fn msgpack(self) -> Self {
+        self.encoding(MediaType::MsgPack)
+    }
+
+    #[cfg(feature = "messagepack")]
+    #[inline]
+    #[must_use]
+    pub fn msgpack_with_response(self) -> Self {
+        self.encoding_with_response(MediaType::MsgPack)
+    }
+
     #[cfg(feature = "postcard")]
     #[inline]
     #[must_use]
@@ -211,6 +598,11 @@ impl<'a> Request<'a> {
         self.with_method(Method::Post)
     }
 
+    #[must_use]
+    pub fn head(self) -> Self {
+        self.with_method(Method::Head)
+    }
+
     #[must_use]
     pub fn retrieve(self) -> Self {
         self.with_method(Method::Get)
@@ -221,6 +613,11 @@ impl<'a> Request<'a> {
         self.with_method(Method::Put)
     }
 
+    #[must_use]
+    pub fn patch(self) -> Self {
+        self.with_method(Method::Patch)
+    }
+
     #[must_use]
     pub fn delete(self) -> Self {
         self.with_method(Method::Delete)
@@ -231,6 +628,11 @@ impl<'a> Request<'a> {
         self.with_method(Method::Post)
     }
 
+    #[must_use]
+    pub fn options(self) -> Self {
+        self.with_method(Method::Options)
+    }
+
     pub fn logging(&self) -> bool {
         self.logging
     }
@@ -247,6 +649,62 @@ impl<'a> Request<'a> {
         self.url
     }
 
+    pub fn query(&self) -> Option<&[(SmolStr, SmolStr)]> {
+        self.query.as_deref()
+    }
+
+    fn joined_url(&self) -> SmolStr {
+        match &self.base {
+            Some(base)
+                if !self.url.starts_with("http://")
+                    && !self.url.starts_with("https://")
+                    && !self.url.starts_with('/') =>
+            {
+                let mut builder = SmolStrBuilder::new();
+                builder.push_str(base.trim_end_matches('/'));
+                if !self.url.is_empty() {
+                    builder.push('/');
+                    builder.push_str(self.url);
+                }
+                builder.finish()
+            }
+            _ => self.url.to_smolstr(),
+        }
+    }
+
+    pub fn full_url(&self) -> SmolStr {
+        let joined = self.joined_url();
+        let Some(query) = self.query.as_deref().filter(|query| !query.is_empty()) else {
+            return joined;
+        };
+
+        let (url, fragment) = match joined.split_once('#') {
+            Some((url, fragment)) => (url, Some(fragment)),
+            None => (joined.as_str(), None),
+        };
+
+        let mut builder = SmolStrBuilder::new();
+        builder.push_str(url);
+        if !url.contains('?') {
+            builder.push('?');
+        } else if !url.ends_with('?') && !url.ends_with('&') {
+            builder.push('&');
+        }
+        for (index, (key, value)) in query.iter().enumerate() {
+            if index > 0 {
+                builder.push('&');
+            }
+            encode_uri_component(&mut builder, key);
+            builder.push('=');
+            encode_uri_component(&mut builder, value);
+        }
+        if let Some(fragment) = fragment {
+            builder.push('#');
+            builder.push_str(fragment);
+        }
+        builder.finish()
+    }
+
     pub fn media_type(&self) -> Option<MediaType> {
         self.media_type
     }
@@ -255,24 +713,65 @@ impl<'a> Request<'a> {
         self.headers.as_deref()
     }
 
+    pub fn body_size(&self) -> Option<u64> {
+        match self.body.as_ref()? {
+            Body::Bytes(bytes) => Some(bytes.len() as u64),
+            #[cfg(feature = "browser")]
+            Body::File(file) => Some(file.size()),
+            #[cfg(feature = "browser")]
+            Body::Stream(_) => None,
+        }
+    }
+
     pub fn wants_response(&self) -> bool {
         self.wants_response
     }
 
+    pub fn dedupe_inflight(&self) -> bool {
+        self.dedupe_inflight
+    }
+
+    pub fn empty_means_none(&self) -> bool {
+        self.empty_means_none
+    }
+
+    pub fn base64_body(&self) -> bool {
+        self.base64_body
+    }
+
+    fn deserialize_mode(&self) -> DeserializeMode {
+        if self.base64_body {
+            DeserializeMode::Base64AndDeserialize
+        } else {
+            DeserializeMode::Deserialize
+        }
+    }
+
+    #[cfg(feature = "browser")]
     pub(crate) fn start(&self) -> Result<PendingFetch, SmolStr> {
+        if let Some(error) = &self.body_error {
+            return Err(error.clone());
+        }
+
         let request_init = RequestInit::new();
-        request_init.set_method(match &self.method {
-            Method::Head => "HEAD",
-            Method::Get => "GET",
-            Method::Post => "POST",
-            Method::Put => "PUT",
-            Method::Delete => "DELETE",
-            Method::Options => "OPTIONS",
-        });
+        request_init.set_method(self.method.verb());
 
         let headers: Headers = self.try_into()?;
         request_init.set_headers(&headers);
 
+        if let Some(credentials) = self.credentials {
+            request_init.set_credentials(credentials);
+        }
+        if let Some(mode) = self.mode {
+            request_init.set_mode(mode);
+        }
+        if let Some(cache) = self.cache {
+            request_init.set_cache(cache);
+        }
+        if let Some(redirect) = self.redirect {
+            request_init.set_redirect(redirect);
+        }
+
         if let Some(body) = &self.body {
             let value = match body {
                 Body::Bytes(bytes) => {
@@ -280,6 +779,11 @@ impl<'a> Request<'a> {
                     JsValue::from(array)
                 }
                 Body::File(file) => JsValue::from(web_sys::File::from(file.clone())),
+                Body::Stream(stream) => {
+                    Reflect::set(&request_init, &"duplex".into(), &"half".into())
+                        .map_err(js_error)?;
+                    JsValue::from(stream)
+                }
             };
             request_init.set_body(&value);
         }
@@ -287,18 +791,194 @@ impl<'a> Request<'a> {
         let abort = Abort::new()?;
         request_init.set_signal(Some(&abort.signal()));
 
+        if let Some(init_hook) = &self.init_hook {
+            init_hook(&request_init);
+        }
+
+        let url = self.full_url();
         let promise = web_sys::window()
             .expect("window")
-            .fetch_with_str_and_init(self.url(), &request_init);
-        Ok(PendingFetch::new(
-            self.url(),
-            abort,
-            self.timeout,
-            JsFuture::from(promise),
-        ))
+            .fetch_with_str_and_init(&url, &request_init);
+        Ok(
+            PendingFetch::new(url, abort, self.timeout.duration(), JsFuture::from(promise))
+                .with_external_abort(self.abort_signal.clone())
+                .with_external_abort(self.abort.as_ref().map(Abort::signal))
+                .with_deserialize_mode(self.deserialize_mode()),
+        )
+    }
+
+    #[cfg(not(feature = "browser"))]
+    pub(crate) fn start(&self) -> Result<PendingFetch, SmolStr> {
+        if let Some(error) = &self.body_error {
+            return Err(error.clone());
+        }
+
+        let abort = Abort::new()?;
+        let url = self.full_url();
+        let timeout = self.timeout.duration();
+        let request_future = native::send(
+            self.method.verb(),
+            url.clone(),
+            self.headers().map(<[_]>::to_vec).unwrap_or_default(),
+            self.body.as_ref().map(|Body::Bytes(bytes)| bytes.clone()),
+            timeout,
+        );
+        Ok(PendingFetch::new(url, abort, timeout, request_future)
+            .with_external_abort(self.abort.clone())
+            .with_deserialize_mode(self.deserialize_mode()))
+    }
+
+    // fetch cannot report progress of request body, so XMLHttpRequest is used instead,
+    // its result is converted to Response to be decoded as any other fetch response
+    #[cfg(feature = "browser")]
+    pub(crate) fn start_with_progress<P>(&self, mut progress: P) -> Result<PendingFetch, SmolStr>
+    where
+        P: FnMut(f64) + 'static,
+    {
+        if let Some(error) = &self.body_error {
+            return Err(error.clone());
+        }
+        if let Some(Body::Stream(_)) = &self.body {
+            return Err("Stream body cannot be sent with progress".into());
+        }
+
+        let url = self.full_url();
+        let xhr = XmlHttpRequest::new().map_err(js_error)?;
+        xhr.open_with_async(self.method.verb(), &url, true)
+            .map_err(js_error)?;
+        if let Some(headers) = self.headers() {
+            for (name, value) in headers {
+                xhr.set_request_header(name, value).map_err(js_error)?;
+            }
+        }
+        xhr.set_response_type(XmlHttpRequestResponseType::Arraybuffer);
+        if self.credentials == Some(RequestCredentials::Include) {
+            xhr.set_with_credentials(true);
+        }
+
+        let on_progress = Closure::<dyn FnMut(ProgressEvent)>::new(move |event: ProgressEvent| {
+            if event.length_computable() && event.total() > 0.0 {
+                progress(event.loaded() / event.total());
+            }
+        });
+        xhr.upload()
+            .map_err(js_error)?
+            .set_onprogress(Some(on_progress.as_ref().unchecked_ref()));
+
+        let abort = Abort::new()?;
+        let on_abort = Closure::<dyn FnMut()>::new({
+            let xhr = xhr.clone();
+            move || {
+                let _ = xhr.abort();
+            }
+        });
+        // listener, so that other handlers of a shared signal are kept
+        let signal = abort.signal();
+        signal
+            .add_event_listener_with_callback("abort", on_abort.as_ref().unchecked_ref())
+            .map_err(js_error)?;
+
+        let mut callbacks = None;
+        let promise = Promise::new(&mut |resolve: Function, reject: Function| {
+            let on_load = Closure::<dyn FnMut()>::new({
+                let xhr = xhr.clone();
+                let reject = reject.clone();
+                move || {
+                    let _ = match xhr_response(&xhr) {
+                        Ok(response) => resolve.call1(&JsValue::UNDEFINED, &response),
+                        Err(error) => reject.call1(&JsValue::UNDEFINED, &error),
+                    };
+                }
+            });
+            let on_failure = Closure::<dyn FnMut()>::new(move || {
+                let error = js_sys::Error::new("XMLHttpRequest failed");
+                let _ = reject.call1(&JsValue::UNDEFINED, &error);
+            });
+            xhr.set_onload(Some(on_load.as_ref().unchecked_ref()));
+            xhr.set_onerror(Some(on_failure.as_ref().unchecked_ref()));
+            xhr.set_onabort(Some(on_failure.as_ref().unchecked_ref()));
+            xhr.set_ontimeout(Some(on_failure.as_ref().unchecked_ref()));
+            callbacks = Some([on_load, on_failure]);
+        });
+        let handlers = XhrHandlers {
+            xhr: xhr.clone(),
+            signal,
+            _on_progress: on_progress,
+            on_abort,
+            _callbacks: callbacks,
+        };
+
+        match &self.body {
+            Some(Body::Bytes(bytes)) => xhr.send_with_opt_u8_array(Some(bytes)),
+            Some(Body::File(file)) => {
+                xhr.send_with_opt_blob(Some(&web_sys::File::from(file.clone())))
+            }
+            Some(Body::Stream(_)) | None => xhr.send(),
+        }
+        .map_err(js_error)?;
+
+        let pending_fetch =
+            PendingFetch::new(url, abort, self.timeout.duration(), JsFuture::from(promise));
+        Ok(pending_fetch
+            .with_handlers(handlers)
+            .with_external_abort(self.abort_signal.clone())
+            .with_external_abort(self.abort.as_ref().map(Abort::signal))
+            .with_deserialize_mode(self.deserialize_mode()))
     }
 }
 
+#[cfg(feature = "browser")]
+struct XhrHandlers {
+    xhr: XmlHttpRequest,
+    signal: AbortSignal,
+    _on_progress: Closure<dyn FnMut(ProgressEvent)>,
+    on_abort: Closure<dyn FnMut()>,
+    _callbacks: Option<[Closure<dyn FnMut()>; 2]>,
+}
+
+#[cfg(feature = "browser")]
+impl Drop for XhrHandlers {
+    fn drop(&mut self) {
+        // detach callbacks, so that JS never calls them once dropped
+        let _ = self
+            .signal
+            .remove_event_listener_with_callback("abort", self.on_abort.as_ref().unchecked_ref());
+        self.xhr.set_onload(None);
+        self.xhr.set_onerror(None);
+        self.xhr.set_onabort(None);
+        self.xhr.set_ontimeout(None);
+        if let Ok(upload) = self.xhr.upload() {
+            upload.set_onprogress(None);
+        }
+    }
+}
+
+#[cfg(feature = "browser")]
+fn xhr_response(xhr: &XmlHttpRequest) -> Result<Response, JsValue> {
+    let status = xhr.status()?;
+    let headers = Headers::new()?;
+    for line in xhr.get_all_response_headers()?.split("\r\n") {
+        if let Some((name, value)) = line.split_once(':') {
+            headers.append(name.trim(), value.trim())?;
+        }
+    }
+
+    let init = ResponseInit::new();
+    init.set_status(status);
+    init.set_headers(&headers);
+
+    // null body statuses must not be constructed with body
+    let body = match status {
+        101 | 103 | 204 | 205 | 304 => None,
+        _ => Some(xhr.response()?),
+    };
+    Response::new_with_opt_buffer_source_and_init(
+        body.as_ref().map(JsCast::unchecked_ref::<Object>),
+        &init,
+    )
+}
+
+#[cfg(feature = "browser")]
 impl TryFrom<&Request<'_>> for Headers {
     type Error = SmolStr;
 
@@ -312,3 +992,161 @@ impl TryFrom<&Request<'_>> for Headers {
         Ok(output)
     }
 }
+
+fn encode_uri_component(builder: &mut SmolStrBuilder, value: &str) {
+    const HEX: &[u8; 16] = b"0123456789ABCDEF";
+
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                builder.push(byte as char)
+            }
+            _ => {
+                builder.push('%');
+                builder.push(HEX[(byte >> 4) as usize] as char);
+                builder.push(HEX[(byte & 0x0f) as usize] as char);
+            }
+        }
+    }
+}
+
+fn encode_form_component(builder: &mut SmolStrBuilder, value: &str) {
+    for (index, part) in value.split(' ').enumerate() {
+        if index > 0 {
+            builder.push('+');
+        }
+        encode_uri_component(builder, part);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn json_value_body() {
+        let request = Request::new("/json").with_json_value(&serde_json::json!({"id": 7}));
+        assert_eq!(Some(MediaType::Json), request.media_type());
+        let Some(Body::Bytes(body)) = &request.body else {
+            panic!("json body expected");
+        };
+        assert_eq!(br#"{"id":7}"#.as_slice(), body.as_slice());
+        assert!(request.body_error.is_none());
+    }
+
+    #[cfg(feature = "postcard")]
+    #[test]
+    fn postcard_value_error_is_kept() {
+        struct Failing;
+
+        impl Serialize for Failing {
+            fn serialize<S: serde::Serializer>(&self, _: S) -> Result<S::Ok, S::Error> {
+                Err(serde::ser::Error::custom("failing"))
+            }
+        }
+
+        let request = Request::new("/postcard").with_postcard_value(&Failing);
+        assert!(request.body.is_none());
+        assert!(request.body_error.is_some());
+    }
+
+    #[test]
+    fn method_from_str() {
+        assert_eq!(Ok(Method::Get), "GET".parse());
+        assert_eq!(Ok(Method::Delete), "delete".parse());
+        assert_eq!(Ok(Method::Options), Method::try_from("OpTiOnS"));
+        assert!("TRACE".parse::<Method>().is_err());
+        assert!(Method::try_from("").is_err());
+    }
+
+    #[test]
+    fn form_urlencoded_body() {
+        let request = Request::new("/form").with_form_urlencoded([
+            ("name", "John Doe".into()),
+            ("query", "a&b=c+d/e?".into()),
+            ("empty", "".into()),
+        ]);
+        assert_eq!(Some(MediaType::Form), request.media_type());
+        let Some(Body::Bytes(body)) = &request.body else {
+            panic!("form body expected");
+        };
+        assert_eq!(
+            "name=John+Doe&query=a%26b%3Dc%2Bd%2Fe%3F&empty=",
+            std::str::from_utf8(body).unwrap()
+        );
+    }
+
+    #[test]
+    fn clone_keeps_template() {
+        let template = Request::new("/items")
+            .with_method(Method::Post)
+            .with_query_param("page", 2)
+            .with_body(vec![1, 2, 3]);
+        let request = template.clone().with_query_param("size", 10);
+        assert_eq!(Method::Post, *template.method());
+        assert_eq!("/items?page=2", template.full_url());
+        assert_eq!("/items?page=2&size=10", request.full_url());
+        let Some(Body::Bytes(body)) = &request.body else {
+            panic!("bytes body expected");
+        };
+        assert_eq!(&[1, 2, 3], body.as_slice());
+    }
+
+    #[test]
+    fn default_timeout_override() {
+        let request = Request::new("/");
+        assert_eq!(Some(Request::DEFAULT_TIMEOUT), request.timeout.duration());
+        set_default_request_timeout(Duration::from_secs(30));
+        assert_eq!(Some(Duration::from_secs(30)), request.timeout.duration());
+        let request = request.with_timeout(Duration::from_secs(1));
+        assert_eq!(Some(Duration::from_secs(1)), request.timeout.duration());
+        let request = request.with_timeout(Some(Duration::from_secs(2)));
+        assert_eq!(Some(Duration::from_secs(2)), request.timeout.duration());
+        assert_eq!(None, request.clone().with_timeout(None).timeout.duration());
+        assert_eq!(None, request.with_timeout(Timeout::None).timeout.duration());
+    }
+
+    #[test]
+    fn base_url_is_joined() {
+        let relative = Request::new("items").with_base("https://api.example.com/v1/");
+        assert_eq!("https://api.example.com/v1/items", relative.full_url());
+        let rooted = Request::new("/items").with_base("https://api.example.com/v1");
+        assert_eq!("/items", rooted.full_url());
+        let absolute = Request::new("http://other.example.com/items").with_base("/v1");
+        assert_eq!("http://other.example.com/items", absolute.full_url());
+
+        set_default_base_url("/api");
+        let request = Request::new("items").with_query_param("page", 2);
+        assert_eq!("/api/items?page=2", request.full_url());
+    }
+
+    #[test]
+    fn set_header_replaces_any_case() {
+        let request = Request::new("/")
+            .with_media_type(MediaType::Json)
+            .set_header("content-type", MediaType::Cbor);
+        assert_eq!(
+            Some(&[("content-type", SmolStr::from("application/cbor"))][..]),
+            request.headers()
+        );
+        let request = request.without_header("CONTENT-TYPE");
+        assert_eq!(Some(&[][..]), request.headers());
+    }
+
+    #[test]
+    fn auth_headers() {
+        let request = Request::new("/")
+            .with_bearer_token("abc")
+            .with_basic_auth("Aladdin", "open sesame");
+        let expected = [(
+            HEADER_AUTHORIZATION,
+            "Basic QWxhZGRpbjpvcGVuIHNlc2FtZQ==".into(),
+        )];
+        assert_eq!(Some(&expected[..]), request.headers());
+
+        let request = Request::new("/").with_bearer_token("abc");
+        let expected = [(HEADER_AUTHORIZATION, "Bearer abc".into())];
+        assert_eq!(Some(&expected[..]), request.headers());
+    }
+}
@@ -0,0 +1,229 @@
+use std::cell::RefCell;
+
+use futures_signals::{
+    signal::{Mutable, Signal},
+    signal_vec::{MutableVec, MutableVecLockRef, SignalVec, SignalVecExt},
+};
+use log::{debug, warn};
+use smol_str::SmolStr;
+use wasm_bindgen::{JsCast, closure::Closure};
+use web_sys::{Event, EventSource, MessageEvent};
+
+use crate::{MediaType, NoMac};
+
+use super::{
+    common::{DeserializeMode, FetchDeserializable, deserialize_content},
+    js_error,
+};
+
+// the browser reconnects on its own after a connection error
+pub struct SseStore<E> {
+    logging: bool,
+    capacity: usize,
+    connected: Mutable<bool>,
+    events: MutableVec<E>,
+    connection: RefCell<Option<Connection>>,
+}
+
+struct Connection {
+    source: EventSource,
+    _on_open: Closure<dyn FnMut(Event)>,
+    _on_message: Closure<dyn FnMut(MessageEvent)>,
+    _on_error: Closure<dyn FnMut(Event)>,
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        self.source.set_onopen(None);
+        self.source.set_onmessage(None);
+        self.source.set_onerror(None);
+        self.source.close();
+    }
+}
+
+impl<E> SseStore<E> {
+    pub fn new() -> Self {
+        Self {
+            logging: true,
+            capacity: 1,
+            connected: Mutable::new(false),
+            events: MutableVec::new(),
+            connection: RefCell::new(None),
+        }
+    }
+
+    #[must_use]
+    pub fn with_logging(mut self, logging: bool) -> Self {
+        self.logging = logging;
+        self
+    }
+
+    // events kept by the store, the oldest ones are dropped first, only the latest by default
+    #[must_use]
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity.max(1);
+        self
+    }
+
+    // previously opened connection is closed
+    pub fn open(&self, url: &str) -> Result<(), SmolStr>
+    where
+        E: Clone + FetchDeserializable + 'static,
+    {
+        self.close();
+        if self.logging {
+            debug!("Opening event source {url}");
+        }
+
+        let source = EventSource::new(url).map_err(js_error)?;
+
+        let on_open = Closure::<dyn FnMut(Event)>::new({
+            let connected = self.connected.clone();
+            move |_| connected.set_neq(true)
+        });
+        let on_message = Closure::<dyn FnMut(MessageEvent)>::new({
+            let logging = self.logging;
+            let capacity = self.capacity;
+            let events = self.events.clone();
+            move |event: MessageEvent| {
+                let decoded = deserialize_content::<E, NoMac>(
+                    MediaType::Json,
+                    DeserializeMode::Deserialize,
+                    event.data(),
+                    None,
+                    None,
+                );
+                match decoded {
+                    Ok(Some(value)) => push_bounded(&events, value, capacity),
+                    Ok(None) => (),
+                    Err((_, error)) => {
+                        if logging {
+                            warn!("Event decoding failed, error: {error}");
+                        }
+                    }
+                }
+            }
+        });
+        let on_error = Closure::<dyn FnMut(Event)>::new({
+            let logging = self.logging;
+            let connected = self.connected.clone();
+            move |_| {
+                if logging {
+                    debug!("Event source connection failed");
+                }
+                connected.set_neq(false);
+            }
+        });
+        source.set_onopen(Some(on_open.as_ref().unchecked_ref()));
+        source.set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+        source.set_onerror(Some(on_error.as_ref().unchecked_ref()));
+
+        self.connection.replace(Some(Connection {
+            source,
+            _on_open: on_open,
+            _on_message: on_message,
+            _on_error: on_error,
+        }));
+        Ok(())
+    }
+
+    pub fn close(&self) {
+        if self.connection.take().is_some() {
+            self.connected.set_neq(false);
+        }
+    }
+
+    pub fn connected(&self) -> bool {
+        self.connected.get()
+    }
+
+    pub fn connected_signal(&self) -> impl Signal<Item = bool> + use<E> {
+        self.connected.signal()
+    }
+
+    #[inline]
+    pub fn events(&self) -> &MutableVec<E> {
+        &self.events
+    }
+
+    #[inline]
+    pub fn lock_ref(&self) -> MutableVecLockRef<'_, E> {
+        self.events.lock_ref()
+    }
+
+    pub fn clear(&self) {
+        self.events.lock_mut().clear();
+    }
+}
+
+impl<E> SseStore<E>
+where
+    E: Copy,
+{
+    pub fn latest(&self) -> Option<E> {
+        self.events.lock_ref().last().copied()
+    }
+
+    pub fn signal_vec(&self) -> impl SignalVec<Item = E> + use<E> {
+        self.events.signal_vec()
+    }
+
+    pub fn latest_signal(&self) -> impl Signal<Item = Option<E>> + use<E> {
+        self.events
+            .signal_vec()
+            .to_signal_map(|events| events.last().copied())
+    }
+}
+
+impl<E> SseStore<E>
+where
+    E: Clone,
+{
+    pub fn latest_cloned(&self) -> Option<E> {
+        self.events.lock_ref().last().cloned()
+    }
+
+    pub fn signal_vec_cloned(&self) -> impl SignalVec<Item = E> + use<E> {
+        self.events.signal_vec_cloned()
+    }
+
+    pub fn latest_signal_cloned(&self) -> impl Signal<Item = Option<E>> + use<E> {
+        self.events
+            .signal_vec_cloned()
+            .to_signal_map(|events| events.last().cloned())
+    }
+}
+
+impl<E> Default for SseStore<E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn push_bounded<E: Clone>(events: &MutableVec<E>, value: E, capacity: usize) {
+    let mut events = events.lock_mut();
+    let excess = (events.len() + 1).saturating_sub(capacity);
+    for _ in 0..excess.min(events.len()) {
+        events.remove(0);
+    }
+    events.push_cloned(value);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn events_bounded_by_capacity() {
+        let events = MutableVec::new();
+        for value in 1..=3 {
+            push_bounded(&events, value, 1);
+        }
+        assert_eq!(vec![3], events.lock_ref().to_vec());
+
+        for value in 4..=6 {
+            push_bounded(&events, value, 2);
+        }
+        assert_eq!(vec![5, 6], events.lock_ref().to_vec());
+    }
+}
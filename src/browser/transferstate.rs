@@ -1,6 +1,8 @@
+use serde::{Deserialize, Serialize};
+
 use crate::StatusCode;
 
-#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum TransferState {
     #[default]
     Empty,
@@ -64,6 +66,13 @@ impl TransferState {
         !matches!(*self, Self::Loaded(status) | Self::Stored(status) if status.is_failure())
     }
 
+    pub fn error_status(&self) -> Option<StatusCode> {
+        match *self {
+            Self::Loaded(status) | Self::Stored(status) if status.is_failure() => Some(status),
+            _ => None,
+        }
+    }
+
     pub fn reset_error(&mut self) {
         *self = match self {
             Self::Loaded(..) => Self::Loaded(StatusCode::Ok),
@@ -80,6 +89,18 @@ impl TransferState {
         *self = Self::PendingStore;
     }
 
+    pub(crate) fn try_start(&mut self, is_load: bool, exclusive: bool) -> bool {
+        if exclusive && self.pending() {
+            return false;
+        }
+        if is_load {
+            self.start_load();
+        } else {
+            self.start_store();
+        }
+        true
+    }
+
     pub(crate) fn stop(&mut self, status: StatusCode) {
         *self = match *self {
             Self::PendingLoad | Self::Loaded(..) => Self::Loaded(status),
@@ -128,3 +149,38 @@ impl OperationState {
         !self.error()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn error_status_of_failure_only() {
+        assert_eq!(None, TransferState::Empty.error_status());
+        assert_eq!(None, TransferState::PendingLoad.error_status());
+        assert_eq!(None, TransferState::Loaded(StatusCode::Ok).error_status());
+        assert_eq!(
+            Some(StatusCode::Forbidden),
+            TransferState::Loaded(StatusCode::Forbidden).error_status()
+        );
+        assert_eq!(
+            Some(StatusCode::InternalServerError),
+            TransferState::Stored(StatusCode::InternalServerError).error_status()
+        );
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn serde_roundtrip() {
+        let json = serde_json::to_string(&TransferState::Loaded(StatusCode::NotFound)).unwrap();
+        assert_eq!(r#"{"Loaded":404}"#, json);
+        assert_eq!(
+            TransferState::Loaded(StatusCode::NotFound),
+            serde_json::from_str(&json).unwrap()
+        );
+        assert_eq!(
+            TransferState::Empty,
+            serde_json::from_str(r#""Empty""#).unwrap()
+        );
+    }
+}
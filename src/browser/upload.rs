@@ -7,19 +7,36 @@ use smol_str::SmolStr;
 use crate::{Messages, NoMac, StatusCode};
 
 use super::{fetch, request::Request, transferstate::TransferState};
+#[cfg(feature = "browser")]
+use super::{fetch_started, start_transfer};
 
-#[derive(Default)]
 pub struct UploadStore {
     transfer_state: Mutable<TransferState>,
+    transfer_generation: Mutable<u64>,
+    logging: bool,
 }
 
 impl UploadStore {
     pub fn new() -> Self {
         Self {
             transfer_state: Mutable::new(TransferState::Empty),
+            transfer_generation: Mutable::new(0),
+            logging: true,
         }
     }
 
+    // `false` disables logging of all requests of the store
+    #[must_use]
+    pub fn with_logging(mut self, logging: bool) -> Self {
+        self.logging = logging;
+        self
+    }
+
+    fn apply_logging<'a>(&self, request: Request<'a>) -> Request<'a> {
+        let logging = self.logging && request.logging();
+        request.with_logging(logging)
+    }
+
     pub fn invalidate(&self) {
         self.transfer_state.set(TransferState::Empty);
     }
@@ -65,6 +82,39 @@ impl UploadStore {
         self.do_store::<SmolStr, _>(request, None, response_messages, result_callback)
     }
 
+    // larger body completes immediately with `StatusCode::PayloadTooBig`
+    pub fn store_with_limit<C>(
+        &self,
+        request: Request<'_>,
+        max_bytes: u64,
+        response_messages: Messages,
+        result_callback: C,
+    ) where
+        C: FnOnce(StatusCode) + 'static,
+    {
+        let request = self.apply_logging(request);
+        if let Some(size) = request.body_size()
+            && size > max_bytes
+        {
+            if request.logging() {
+                debug!(
+                    "Request to store {} refused, body of {size} bytes exceeds {max_bytes}",
+                    request.url()
+                );
+            }
+            response_messages.replace(Messages::from_service_error_with_pars(
+                "Request failed as payload of {0} bytes exceeds {1} bytes",
+                [size, max_bytes],
+            ));
+            self.transfer_state
+                .set(TransferState::Stored(StatusCode::PayloadTooBig));
+            result_callback(StatusCode::PayloadTooBig);
+            return;
+        }
+
+        self.store(request, response_messages, result_callback);
+    }
+
     pub fn store_with_response<R, C>(
         &self,
         request: Request<'_>,
@@ -83,6 +133,39 @@ impl UploadStore {
         );
     }
 
+    #[cfg(feature = "browser")]
+    // XMLHttpRequest is used, so that upload progress can be reported
+    pub fn store_with_progress<C, P>(
+        &self,
+        request: Request<'_>,
+        response_messages: Messages,
+        progress: P,
+        result_callback: C,
+    ) where
+        C: FnOnce(StatusCode) + 'static,
+        P: FnMut(f64) + 'static,
+    {
+        let request = self.apply_logging(request);
+        if request.logging() {
+            debug!("Request to store {} with progress", request.url());
+        }
+
+        if !start_transfer(&request, &self.transfer_state) {
+            return;
+        }
+
+        fetch_started::<SmolStr, _, NoMac>(
+            &request,
+            request.start_with_progress(progress),
+            self.transfer_state.clone(),
+            self.transfer_generation.clone(),
+            response_messages,
+            None,
+            move |status, _| result_callback(status),
+        )
+        .detach();
+    }
+
     fn do_store<R, C>(
         &self,
         request: Request<'_>,
@@ -93,15 +176,24 @@ impl UploadStore {
         C: FnOnce(StatusCode) + 'static,
         R: DeserializeOwned + 'static,
     {
+        let request = self.apply_logging(request);
         if request.logging() {
             debug!("Request to store {}", request.url());
         }
         fetch::<_, _, NoMac>(
             request,
             self.transfer_state.clone(),
+            self.transfer_generation.clone(),
             response_messages,
             response_entity,
-            result_callback,
-        );
+            move |status, _| result_callback(status),
+        )
+        .detach();
+    }
+}
+
+impl Default for UploadStore {
+    fn default() -> Self {
+        Self::new()
     }
 }
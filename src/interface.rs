@@ -16,5 +16,10 @@ pub use statuscode::*;
 mod transport;
 pub use transport::*;
 
+pub const HEADER_ALLOW: &str = "Allow";
+pub const HEADER_AUTHORIZATION: &str = "Authorization";
+pub const HEADER_ETAG: &str = "ETag";
+pub const HEADER_IF_NONE_MATCH: &str = "If-None-Match";
+pub const HEADER_RETRY_AFTER: &str = "Retry-After";
 pub const HEADER_SIGNATURE: &str = "Content-Signature";
 pub const HEADER_WANTS_RESPONSE: &str = "Wants-Response";
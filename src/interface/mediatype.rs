@@ -9,14 +9,20 @@ pub enum MediaType {
     ByteStream,
     Cbor,
     Css,
+    Csv,
     Form,
     FormMultipart,
+    Gif,
+    Gzip,
     Html,
     Ico,
     Javascript,
     Jpeg,
     Json,
+    Mp3,
     Mp4,
+    MsgPack,
+    Ndjson,
     Pdf,
     Plain,
     Png,
@@ -26,6 +32,7 @@ pub enum MediaType {
     Svg,
     Urf,
     Wasm,
+    Webp,
     Xml,
     Xlsx,
     Zip,
@@ -36,14 +43,21 @@ const ANY: &str = "*/*";
 const BYTE_STREAM: &str = "application/octet-stream";
 const CBOR: &str = "application/cbor";
 const CSS: &str = "text/css";
+const CSV: &str = "text/csv";
 const FORM: &str = "application/x-www-form-urlencoded";
 const MULTIPART_FORM: &str = "multipart/form-data";
+const GIF: &str = "image/gif";
+const GZIP: &str = "application/gzip";
 const HTML: &str = "text/html";
 const ICO: &str = "image/x-icon";
 const JAVASCRIPT: &str = "application/javascript";
 const JPEG: &str = "image/jpeg";
 const JSON: &str = "application/json";
+const MP3: &str = "audio/mpeg";
 const MP4: &str = "video/mp4";
+const MSGPACK: &str = "application/msgpack";
+const MSGPACK_X: &str = "application/x-msgpack";
+const NDJSON: &str = "application/x-ndjson";
 const PDF: &str = "application/pdf";
 const PLAIN: &str = "text/plain";
 const PNG: &str = "image/png";
@@ -53,6 +67,7 @@ const SSE: &str = "text/event-stream";
 const SVG: &str = "image/svg+xml";
 const URF: &str = "image/urf";
 const WASM: &str = "application/wasm";
+const WEBP: &str = "image/webp";
 const XLSX: &str = "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet";
 const XML: &str = "application/xml";
 const ZIP: &str = "application/zip";
@@ -63,30 +78,114 @@ impl MediaType {
     pub fn as_str(&self) -> &str {
         self.as_ref()
     }
-}
 
-impl Display for MediaType {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.write_str(self.as_ref())
+    // structured text like JSON or SVG included
+    pub fn is_text(&self) -> bool {
+        use MediaType::*;
+
+        matches!(
+            self,
+            Css | Csv | Form | Html | Javascript | Json | Ndjson | Plain | Sse | Svg | Xml
+        )
     }
-}
 
-impl From<&str> for MediaType {
-    fn from(mime: &str) -> Self {
-        match mime {
+    #[inline]
+    pub fn is_binary(&self) -> bool {
+        !self.is_text()
+    }
+
+    // the leading dot is optional
+    pub fn from_extension(extension: &str) -> Self {
+        let extension = extension.trim_start_matches('.');
+        match extension.to_ascii_lowercase().as_str() {
+            "cbor" => Self::Cbor,
+            "css" => Self::Css,
+            "csv" => Self::Csv,
+            "gif" => Self::Gif,
+            "gz" => Self::Gzip,
+            "htm" | "html" => Self::Html,
+            "ico" => Self::Ico,
+            "js" | "mjs" => Self::Javascript,
+            "jpeg" | "jpg" => Self::Jpeg,
+            "json" => Self::Json,
+            "mp3" => Self::Mp3,
+            "mp4" => Self::Mp4,
+            "msgpack" => Self::MsgPack,
+            "ndjson" => Self::Ndjson,
+            "pdf" => Self::Pdf,
+            "txt" => Self::Plain,
+            "png" => Self::Png,
+            "pwg" => Self::Pwg,
+            "svg" => Self::Svg,
+            "urf" => Self::Urf,
+            "wasm" => Self::Wasm,
+            "webp" => Self::Webp,
+            "xml" => Self::Xml,
+            "xlsx" => Self::Xlsx,
+            "zip" => Self::Zip,
+            "7z" => Self::Zip7,
+            _ => Self::default(),
+        }
+    }
+
+    pub fn extension(&self) -> Option<&'static str> {
+        use MediaType::*;
+
+        match self {
+            Any | Form | FormMultipart | Postcard | Sse => None,
+            ByteStream => Some("bin"),
+            Cbor => Some("cbor"),
+            Css => Some("css"),
+            Csv => Some("csv"),
+            Gif => Some("gif"),
+            Gzip => Some("gz"),
+            Html => Some("html"),
+            Ico => Some("ico"),
+            Javascript => Some("js"),
+            Jpeg => Some("jpg"),
+            Json => Some("json"),
+            Mp3 => Some("mp3"),
+            Mp4 => Some("mp4"),
+            MsgPack => Some("msgpack"),
+            Ndjson => Some("ndjson"),
+            Pdf => Some("pdf"),
+            Plain => Some("txt"),
+            Png => Some("png"),
+            Pwg => Some("pwg"),
+            Svg => Some("svg"),
+            Urf => Some("urf"),
+            Wasm => Some("wasm"),
+            Webp => Some("webp"),
+            Xml => Some("xml"),
+            Xlsx => Some("xlsx"),
+            Zip => Some("zip"),
+            Zip7 => Some("7z"),
+        }
+    }
+
+    fn from_essence(essence: &str) -> Self {
+        match essence {
             ANY => Self::Any,
             BYTE_STREAM => Self::ByteStream,
             CBOR => Self::Cbor,
             CSS => Self::Css,
+            CSV => Self::Csv,
             FORM => Self::Form,
             MULTIPART_FORM => Self::FormMultipart,
+            GIF => Self::Gif,
+            GZIP => Self::Gzip,
             HTML => Self::Html,
             ICO => Self::Ico,
             JAVASCRIPT => Self::Javascript,
             JPEG => Self::Jpeg,
             JSON => Self::Json,
+            MP3 => Self::Mp3,
             MP4 => Self::Mp4,
+            MSGPACK => Self::MsgPack,
+            MSGPACK_X => Self::MsgPack,
+            NDJSON => Self::Ndjson,
             PDF => Self::Pdf,
+            PLAIN => Self::Plain,
             PNG => Self::Png,
             POSTCARD => Self::Postcard,
             PWG => Self::Pwg,
@@ -94,6 +193,7 @@ impl From<&str> for MediaType {
             SVG => Self::Svg,
             URF => Self::Urf,
             WASM => Self::Wasm,
+            WEBP => Self::Webp,
             XML => Self::Xml,
             XLSX => Self::Xlsx,
             ZIP => Self::Zip,
@@ -104,6 +204,24 @@ impl From<&str> for MediaType {
     }
 }
 
+impl Display for MediaType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_ref())
+    }
+}
+
+impl From<&str> for MediaType {
+    fn from(mime: &str) -> Self {
+        // parameters (e.g. charset) are not significant for the media type
+        let essence = mime.split(';').next().unwrap_or_default().trim();
+        if essence.bytes().any(|byte| byte.is_ascii_uppercase()) {
+            Self::from_essence(&essence.to_ascii_lowercase())
+        } else {
+            Self::from_essence(essence)
+        }
+    }
+}
+
 impl From<SmolStr> for MediaType {
     fn from(mime: SmolStr) -> Self {
         Self::from(mime.as_str())
@@ -119,14 +237,20 @@ impl AsRef<str> for MediaType {
             ByteStream => BYTE_STREAM,
             Cbor => CBOR,
             Css => CSS,
+            Csv => CSV,
             Form => FORM,
             FormMultipart => MULTIPART_FORM,
+            Gif => GIF,
+            Gzip => GZIP,
             Html => HTML,
             Ico => ICO,
             Javascript => JAVASCRIPT,
             Jpeg => JPEG,
             Json => JSON,
+            Mp3 => MP3,
             Mp4 => MP4,
+            MsgPack => MSGPACK,
+            Ndjson => NDJSON,
             Pdf => PDF,
             Plain => PLAIN,
             Png => PNG,
@@ -136,6 +260,7 @@ impl AsRef<str> for MediaType {
             Svg => SVG,
             Urf => URF,
             Wasm => WASM,
+            Webp => WEBP,
             Xml => XML,
             Xlsx => XLSX,
             Zip => ZIP,
@@ -164,4 +289,101 @@ impl<'de> Deserialize<'de> for MediaType {
 }
 
 pub const HEADER_ACCEPT: &str = "Accept";
+pub const HEADER_CONTENT_ENCODING: &str = "Content-Encoding";
+pub const HEADER_CONTENT_LENGTH: &str = "Content-Length";
 pub const HEADER_CONTENT_TYPE: &str = "Content-Type";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exact_mime_is_parsed() {
+        assert_eq!(MediaType::Json, MediaType::from("application/json"));
+        assert_eq!(MediaType::Plain, MediaType::from("text/plain"));
+        assert_eq!(MediaType::Ndjson, MediaType::from("application/x-ndjson"));
+        assert_eq!(MediaType::MsgPack, MediaType::from("application/msgpack"));
+        assert_eq!(MediaType::MsgPack, MediaType::from("application/x-msgpack"));
+    }
+
+    #[test]
+    fn common_types_round_trip() {
+        for media_type in [
+            MediaType::Csv,
+            MediaType::Gif,
+            MediaType::Gzip,
+            MediaType::Mp3,
+            MediaType::Mp4,
+            MediaType::Webp,
+        ] {
+            assert_eq!(media_type, MediaType::from(media_type.as_str()));
+        }
+    }
+
+    #[test]
+    fn charset_is_ignored() {
+        assert_eq!(
+            MediaType::Json,
+            MediaType::from("application/json; charset=utf-8")
+        );
+        assert_eq!(MediaType::Html, MediaType::from("text/html;charset=UTF-8"));
+    }
+
+    #[test]
+    fn case_is_ignored() {
+        assert_eq!(MediaType::Html, MediaType::from("TEXT/HTML"));
+        assert_eq!(MediaType::Json, MediaType::from("Application/Json"));
+    }
+
+    #[test]
+    fn whitespace_is_trimmed() {
+        assert_eq!(
+            MediaType::Postcard,
+            MediaType::from(" application/x-postcard ")
+        );
+    }
+
+    #[test]
+    fn text_and_binary() {
+        for media_type in [
+            MediaType::Json,
+            MediaType::Html,
+            MediaType::Svg,
+            MediaType::Sse,
+        ] {
+            assert!(media_type.is_text());
+            assert!(!media_type.is_binary());
+        }
+        for media_type in [MediaType::Postcard, MediaType::Png, MediaType::Gzip] {
+            assert!(media_type.is_binary());
+            assert!(!media_type.is_text());
+        }
+    }
+
+    #[test]
+    fn unknown_is_byte_stream() {
+        assert_eq!(
+            MediaType::ByteStream,
+            MediaType::from("application/unknown")
+        );
+        assert_eq!(MediaType::ByteStream, MediaType::from(""));
+    }
+
+    #[test]
+    fn extension_is_mapped() {
+        assert_eq!(MediaType::Png, MediaType::from_extension("png"));
+        assert_eq!(MediaType::Jpeg, MediaType::from_extension(".JPG"));
+        assert_eq!(MediaType::Pdf, MediaType::from_extension("pdf"));
+        assert_eq!(MediaType::ByteStream, MediaType::from_extension("unknown"));
+        assert_eq!(MediaType::ByteStream, MediaType::from_extension(""));
+    }
+
+    #[test]
+    fn extension_round_trips() {
+        for media_type in [MediaType::Json, MediaType::Zip7, MediaType::Xlsx] {
+            let extension = media_type.extension().unwrap();
+            assert_eq!(media_type, MediaType::from_extension(extension));
+        }
+        assert_eq!(None, MediaType::Sse.extension());
+    }
+}
@@ -6,8 +6,8 @@ use std::{
 
 use futures_signals::{
     signal::{Mutable, Signal, SignalExt},
-    signal_map::{MutableBTreeMap, SignalMapExt},
-    signal_vec::{MutableVec, SignalVec},
+    signal_map::{MutableBTreeMap, MutableBTreeMapLockMut, SignalMapExt},
+    signal_vec::{MutableVec, SignalVec, SignalVecExt},
 };
 use futures_signals_ext::{MutableExt, MutableVecExt, SignalExtMapOption};
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
@@ -19,13 +19,62 @@ pub enum MessageType {
     Error,
     Information,
     Section,
+    Warning,
 }
 
-#[derive(Clone, Serialize, Deserialize)]
+// higher is more severe, sections carry no severity
+fn severity_rank(message_type: MessageType) -> Option<u8> {
+    match message_type {
+        MessageType::Section => None,
+        MessageType::Information => Some(1),
+        MessageType::Warning => Some(2),
+        MessageType::Error => Some(3),
+    }
+}
+
+fn remove_type(
+    messages: &mut MutableBTreeMapLockMut<SmolStr, MutableVec<Message>>,
+    key: SmolStr,
+    message_type: MessageType,
+) {
+    let Some(for_key) = messages.get(&key) else {
+        return;
+    };
+    for_key
+        .lock_mut()
+        .retain(|message| message.message_type != message_type);
+    if for_key.lock_ref().is_empty() {
+        messages.remove(&key);
+    }
+}
+
+// messages preceding the first section are not part of any section
+fn group_sections(messages: &[Message]) -> Vec<(Message, Vec<Message>)> {
+    let mut sections: Vec<(Message, Vec<Message>)> = Vec::new();
+    for message in messages {
+        if message.message_type == MessageType::Section {
+            sections.push((message.clone(), Vec::new()));
+        } else if let Some((_, section)) = sections.last_mut() {
+            section.push(message.clone());
+        }
+    }
+    sections
+}
+
+fn max_severity(message_types: impl Iterator<Item = MessageType>) -> Option<MessageType> {
+    message_types
+        .filter_map(|message_type| severity_rank(message_type).map(|rank| (rank, message_type)))
+        .max_by_key(|(rank, _)| *rank)
+        .map(|(_, message_type)| message_type)
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct Message {
     message_type: MessageType,
     text: SmolStr,
     parameters: Vec<SmolStr>,
+    #[serde(default)]
+    named_parameters: BTreeMap<SmolStr, SmolStr>,
 }
 
 impl Message {
@@ -34,6 +83,7 @@ impl Message {
             message_type,
             text: text.to_smolstr(),
             parameters: Vec::new(),
+            named_parameters: BTreeMap::new(),
         }
     }
 
@@ -46,6 +96,15 @@ impl Message {
         self
     }
 
+    #[must_use]
+    pub fn with_named_parameters(
+        mut self,
+        parameters: impl IntoIterator<Item = (SmolStr, SmolStr)>,
+    ) -> Self {
+        self.named_parameters = parameters.into_iter().collect();
+        self
+    }
+
     pub fn message_type(&self) -> MessageType {
         self.message_type
     }
@@ -54,6 +113,10 @@ impl Message {
         self.message_type == MessageType::Error
     }
 
+    pub fn warning(&self) -> bool {
+        self.message_type == MessageType::Warning
+    }
+
     pub fn text(&self) -> &str {
         &self.text
     }
@@ -62,31 +125,58 @@ impl Message {
         &self.parameters
     }
 
+    pub fn named_parameters(&self) -> &BTreeMap<SmolStr, SmolStr> {
+        &self.named_parameters
+    }
+
     pub fn localize<T>(&self, t: T) -> Self
     where
         T: Fn(&str) -> SmolStr,
     {
-        let localized = t(self.text());
-        let localized = if self.parameters().is_empty() {
-            localized
-        } else {
-            let mut expanded = localized.to_string();
-            for (index, parameter) in self.parameters().iter().enumerate() {
-                expanded = expanded.replace(format_smolstr!("{{{index}}}").as_str(), parameter);
-            }
-            expanded.into()
-        };
+        self.localized(t(self.text()))
+    }
+
+    pub fn try_localize<T>(&self, t: T) -> std::result::Result<Self, SmolStr>
+    where
+        T: Fn(&str) -> std::result::Result<SmolStr, SmolStr>,
+    {
+        t(self.text()).map(|localized| self.localized(localized))
+    }
+
+    // parameters are substituted as is, without localization
+    pub fn interpolate(&self) -> SmolStr {
+        self.expand(self.text.clone())
+    }
+
+    fn localized(&self, localized: SmolStr) -> Self {
         Self {
             message_type: self.message_type,
-            text: localized,
+            text: self.expand(localized),
             parameters: vec![],
+            named_parameters: BTreeMap::new(),
         }
     }
+
+    fn expand(&self, text: SmolStr) -> SmolStr {
+        if self.parameters().is_empty() && self.named_parameters().is_empty() {
+            return text;
+        }
+
+        let mut expanded = text.to_string();
+        for (index, parameter) in self.parameters().iter().enumerate() {
+            expanded = expanded.replace(format_smolstr!("{{{index}}}").as_str(), parameter);
+        }
+        for (name, parameter) in self.named_parameters() {
+            expanded = expanded.replace(format_smolstr!("{{{name}}}").as_str(), parameter);
+        }
+        expanded.into()
+    }
 }
 
 #[derive(Default, Clone)]
 pub struct Messages {
     error: Mutable<bool>,
+    warning: Mutable<bool>,
     messages: MutableBTreeMap<SmolStr, MutableVec<Message>>,
 }
 
@@ -107,6 +197,7 @@ impl<'de> Deserialize<'de> for Messages {
         <MutableBTreeMap<SmolStr, MutableVec<Message>>>::deserialize(deserializer).map(|messages| {
             Self {
                 error: Mutable::new(false),
+                warning: Mutable::new(false),
                 messages,
             }
         })
@@ -135,6 +226,7 @@ impl Debug for Messages {
                     MessageType::Error => "E: ",
                     MessageType::Information => "I: ",
                     MessageType::Section => "S: ",
+                    MessageType::Warning => "W: ",
                 })?;
                 f.write_str(message.text())?;
             }
@@ -159,6 +251,7 @@ impl Messages {
     pub fn new() -> Messages {
         Self {
             error: Mutable::new(false),
+            warning: Mutable::new(false),
             messages: MutableBTreeMap::new(),
         }
     }
@@ -166,10 +259,12 @@ impl Messages {
     pub fn replace(&self, with: Messages) {
         self.lock_mut().replace_cloned(with.lock_mut().clone());
         self.evaluate_error();
+        self.evaluate_warning();
     }
 
     pub fn extend(&self, with: Messages) {
         self.error.inspect_mut(|this| *this |= with.error.get());
+        self.warning.inspect_mut(|this| *this |= with.warning.get());
         let mut this = self.lock_mut();
         let mut from = with.lock_mut();
         let from_keys = from.keys().cloned().collect::<Vec<_>>();
@@ -188,6 +283,7 @@ impl Messages {
     pub fn from_inner(inner: BTreeMap<SmolStr, MutableVec<Message>>) -> Self {
         Self {
             error: Mutable::new(false),
+            warning: Mutable::new(false),
             messages: MutableBTreeMap::with_values(inner),
         }
     }
@@ -196,6 +292,24 @@ impl Messages {
         self.messages.lock_ref().deref().clone()
     }
 
+    // `f` must not modify the messages
+    pub fn for_each<F>(&self, mut f: F)
+    where
+        F: FnMut(&str, &Message),
+    {
+        for (key, messages) in self.messages.lock_ref().iter() {
+            for message in messages.lock_ref().iter() {
+                f(key, message);
+            }
+        }
+    }
+
+    pub fn collect(&self) -> Vec<(SmolStr, Message)> {
+        let mut collected = vec![];
+        self.for_each(|key, message| collected.push((key.into(), message.clone())));
+        collected
+    }
+
     #[must_use]
     fn with(
         self,
@@ -225,9 +339,46 @@ impl Messages {
         self.error.signal().dedupe()
     }
 
+    pub fn warning(&self) -> bool {
+        self.warning.get()
+    }
+
+    fn evaluate_warning(&self) {
+        let warning = self
+            .messages
+            .lock_ref()
+            .values()
+            .any(|messages| messages.lock_ref().iter().any(Message::warning));
+        self.warning.set_neq(warning);
+    }
+
+    pub fn warning_signal(&self) -> impl Signal<Item = bool> + use<> {
+        self.warning.signal().dedupe()
+    }
+
+    // sections are ignored
+    pub fn max_severity(&self) -> Option<MessageType> {
+        max_severity(self.messages.lock_ref().values().filter_map(|messages| {
+            max_severity(messages.lock_ref().iter().map(Message::message_type))
+        }))
+    }
+
+    pub fn max_severity_signal(&self) -> impl Signal<Item = Option<MessageType>> + use<> {
+        self.messages
+            .entries_cloned()
+            .map_signal(|(_, messages)| {
+                messages.signal_vec_cloned().to_signal_map(|messages| {
+                    max_severity(messages.iter().map(Message::message_type))
+                })
+            })
+            .to_signal_map(|severities| max_severity(severities.iter().flatten().copied()))
+            .dedupe()
+    }
+
     pub fn clear_all(&self) {
         self.messages.lock_mut().clear();
         self.error.set_neq(false);
+        self.warning.set_neq(false);
     }
 
     pub fn set(&self, key: impl ToSmolStr, message_type: MessageType, message: impl ToSmolStr) {
@@ -246,6 +397,7 @@ impl Messages {
             .lock_mut()
             .insert_cloned(key.to_smolstr(), MutableVec::new_with_values(vec![message]));
         self.error.set_neq(message_type == MessageType::Error);
+        self.warning.set_neq(message_type == MessageType::Warning);
     }
 
     pub fn add(&self, key: impl ToSmolStr, message_type: MessageType, text: impl ToSmolStr) {
@@ -269,11 +421,57 @@ impl Messages {
         }
         self.error
             .set_neq(self.error.get() || message_type == MessageType::Error);
+        self.warning
+            .set_neq(self.warning.get() || message_type == MessageType::Warning);
+    }
+
+    pub fn add_unique(&self, key: impl ToSmolStr, message_type: MessageType, text: impl ToSmolStr) {
+        self.add_unique_with_pars(key, message_type, text, [""; 0]);
+    }
+
+    pub fn add_unique_with_pars(
+        &self,
+        key: impl ToSmolStr,
+        message_type: MessageType,
+        text: impl ToSmolStr,
+        parameters: impl IntoIterator<Item = impl ToSmolStr>,
+    ) {
+        let key = key.to_smolstr();
+        let message = Message::new(message_type, text).with_parameters(parameters);
+        let exists = self
+            .messages
+            .lock_ref()
+            .get(&key)
+            .is_some_and(|messages| messages.lock_ref().contains(&message));
+        if !exists {
+            self.add_with_pars(key, message.message_type, message.text, message.parameters);
+        }
     }
 
     pub fn clear(&self, key: impl ToSmolStr) {
         self.messages.lock_mut().remove(&key.to_smolstr());
         self.evaluate_error();
+        self.evaluate_warning();
+    }
+
+    // keys left empty are removed
+    pub fn clear_type(&self, message_type: MessageType) {
+        let mut lock = self.messages.lock_mut();
+        let keys = lock.keys().cloned().collect::<Vec<_>>();
+        for key in keys {
+            remove_type(&mut lock, key, message_type);
+        }
+        drop(lock);
+        self.evaluate_error();
+        self.evaluate_warning();
+    }
+
+    // the key is removed when left empty
+    pub fn clear_key_type(&self, key: impl ToSmolStr, message_type: MessageType) {
+        let key = key.to_smolstr();
+        remove_type(&mut self.messages.lock_mut(), key, message_type);
+        self.evaluate_error();
+        self.evaluate_warning();
     }
 
     pub fn anything_for_key_signal<S: ToSmolStr>(
@@ -293,6 +491,34 @@ impl Messages {
             .map_some_default(|messages| messages.lock_ref().iter().any(Message::error))
     }
 
+    pub fn any_for_prefix_signal<S: ToSmolStr>(
+        &self,
+        prefix: S,
+    ) -> impl Signal<Item = bool> + use<S> {
+        let prefix = prefix.to_smolstr();
+        self.messages
+            .entries_cloned()
+            .filter(move |(key, _)| key.starts_with(prefix.as_str()))
+            .map_signal(|(_, messages)| messages.signal_vec_cloned().len())
+            .sum()
+            .map(|count| count > 0)
+            .dedupe()
+    }
+
+    pub fn count_signal(&self) -> impl Signal<Item = usize> + use<> {
+        self.messages
+            .entries_cloned()
+            .map_signal(|(_, messages)| messages.signal_vec_cloned().len())
+            .sum()
+    }
+
+    pub fn error_count_signal(&self) -> impl Signal<Item = usize> + use<> {
+        self.messages
+            .entries_cloned()
+            .map_signal(|(_, messages)| messages.signal_vec_cloned().filter(Message::error).len())
+            .sum()
+    }
+
     pub fn messages_for_key_signal_vec<S: ToSmolStr>(
         &self,
         key: S,
@@ -307,12 +533,25 @@ impl Messages {
             })
     }
 
+    // each section with messages following it under the same key, up to the next section
+    pub fn sections_signal_vec(&self) -> impl SignalVec<Item = (Message, Vec<Message>)> + use<> {
+        self.messages
+            .entries_cloned()
+            .map_signal(|(_, messages)| messages.signal_vec_cloned().to_signal_map(group_sections))
+            .to_signal_map(|sections| sections.iter().flatten().cloned().collect())
+            .to_signal_vec()
+    }
+
     pub fn add_entity_error(&self, message: impl ToSmolStr) {
-        self.add(&Self::ENTITY, MessageType::Error, message)
+        self.add(Self::ENTITY, MessageType::Error, message)
     }
 
     pub fn add_entity_info(&self, message: impl ToSmolStr) {
-        self.add(&Self::ENTITY, MessageType::Information, message)
+        self.add(Self::ENTITY, MessageType::Information, message)
+    }
+
+    pub fn add_entity_warning(&self, message: impl ToSmolStr) {
+        self.add(Self::ENTITY, MessageType::Warning, message)
     }
 
     pub fn add_service_error(&self, message: impl ToSmolStr) {
@@ -323,6 +562,10 @@ impl Messages {
         self.add(Self::SERVICE, MessageType::Information, message)
     }
 
+    pub fn add_service_warning(&self, message: impl ToSmolStr) {
+        self.add(Self::SERVICE, MessageType::Warning, message)
+    }
+
     pub fn from_service_error(message: impl ToSmolStr) -> Self {
         Self::new().with(Self::SERVICE, MessageType::Error, message, [""; 0])
     }
@@ -365,14 +608,39 @@ impl Messages {
 
         Self {
             error: self.error,
+            warning: self.warning,
             messages: MutableBTreeMap::with_values(localized),
         }
     }
+
+    pub fn try_localize<T>(self, t: T) -> std::result::Result<Self, SmolStr>
+    where
+        T: Fn(&str) -> std::result::Result<SmolStr, SmolStr>,
+    {
+        let localized = self
+            .messages
+            .lock_ref()
+            .iter()
+            .map(|(key, messages)| {
+                let localized = messages
+                    .lock_ref()
+                    .iter()
+                    .map(|message| message.try_localize(&t))
+                    .collect::<std::result::Result<Vec<_>, _>>()?;
+                Ok((key.clone(), MutableVec::new_with_values(localized)))
+            })
+            .collect::<std::result::Result<BTreeMap<_, _>, SmolStr>>()?;
+
+        Ok(Self {
+            error: self.error,
+            warning: self.warning,
+            messages: MutableBTreeMap::with_values(localized),
+        })
+    }
 }
 
 #[cfg(test)]
 #[allow(clippy::assertions_on_constants)]
-
 mod tests {
     use super::*;
 
@@ -389,6 +657,133 @@ mod tests {
         assert!(true);
     }
 
+    #[test]
+    fn max_severity_ignores_sections() {
+        let messages = Messages::new();
+        assert!(messages.max_severity().is_none());
+        messages.add("a", MessageType::Section, "S");
+        assert!(messages.max_severity().is_none());
+        messages.add("a", MessageType::Information, "I");
+        messages.add("b", MessageType::Warning, "W");
+        assert!(messages.max_severity() == Some(MessageType::Warning));
+        messages.add("c", MessageType::Error, "E");
+        assert!(messages.max_severity() == Some(MessageType::Error));
+    }
+
+    #[test]
+    fn any_for_prefix() {
+        use std::{
+            pin::Pin,
+            task::{Context, Poll, Waker},
+        };
+
+        let messages = Messages::new();
+        messages.add("name", MessageType::Error, "N");
+        let mut signal = messages.any_for_prefix_signal("address.");
+        let mut poll =
+            || Pin::new(&mut signal).poll_change(&mut Context::from_waker(Waker::noop()));
+        assert_eq!(Poll::Ready(Some(false)), poll());
+
+        messages.add("address.zip", MessageType::Warning, "Z");
+        assert_eq!(Poll::Ready(Some(true)), poll());
+
+        messages.add("address.city", MessageType::Error, "C");
+        assert_eq!(Poll::Pending, poll());
+
+        messages.clear("address.zip");
+        messages.clear("address.city");
+        assert_eq!(Poll::Ready(Some(false)), poll());
+    }
+
+    #[test]
+    fn collect_is_ordered_by_key() {
+        let messages = Messages::new();
+        messages.add("b", MessageType::Warning, "W");
+        messages.add("a", MessageType::Error, "E1");
+        messages.add("a", MessageType::Information, "I");
+        let collected = messages
+            .collect()
+            .into_iter()
+            .map(|(key, message)| (key, message.text().to_smolstr()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                ("a".into(), "E1".into()),
+                ("a".into(), "I".into()),
+                ("b".into(), "W".into())
+            ],
+            collected
+        );
+    }
+
+    #[test]
+    fn interpolate_keeps_message() {
+        let message = Message::new(MessageType::Error, "{0} of {max} exceeds {1}")
+            .with_parameters(["Size", "limit"])
+            .with_named_parameters([("max".into(), "10".into())]);
+        assert_eq!("Size of 10 exceeds limit", message.interpolate());
+        assert_eq!("{0} of {max} exceeds {1}", message.text());
+        assert_eq!(2, message.parameters().len());
+    }
+
+    #[test]
+    fn clear_type_keeps_other_types() {
+        let messages = Messages::new();
+        messages.add_service_error("SE");
+        messages.add_service_info("SI");
+        messages.add_entity_error("EE");
+        messages.add("field", MessageType::Warning, "FW");
+
+        messages.clear_key_type(Messages::SERVICE, MessageType::Error);
+        assert_eq!(
+            "entity: [E: EE], field: [W: FW], service: [I: SI]",
+            format!("{messages:?}")
+        );
+        assert!(messages.error());
+
+        messages.clear_type(MessageType::Error);
+        assert_eq!("field: [W: FW], service: [I: SI]", format!("{messages:?}"));
+        assert!(!messages.error());
+        assert!(messages.warning());
+    }
+
+    #[test]
+    fn sections_group_following_messages() {
+        use std::{
+            pin::Pin,
+            task::{Context, Poll, Waker},
+        };
+
+        use futures_signals::signal_vec::VecDiff;
+
+        let messages = Messages::new();
+        messages.add("a", MessageType::Error, "orphan");
+        messages.add("a", MessageType::Section, "S1");
+        messages.add("a", MessageType::Error, "E1");
+        messages.add("a", MessageType::Warning, "W1");
+        messages.add("a", MessageType::Section, "S2");
+        messages.add("b", MessageType::Section, "S3");
+        messages.add("b", MessageType::Information, "I3");
+
+        let mut sections = messages.sections_signal_vec();
+        let Poll::Ready(Some(VecDiff::Replace { values })) =
+            Pin::new(&mut sections).poll_vec_change(&mut Context::from_waker(Waker::noop()))
+        else {
+            panic!("sections expected");
+        };
+        let texts = values
+            .iter()
+            .map(|(section, messages)| {
+                let texts = messages.iter().map(Message::text).collect::<Vec<_>>();
+                (section.text(), texts)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![("S1", vec!["E1", "W1"]), ("S2", vec![]), ("S3", vec!["I3"])],
+            texts
+        );
+    }
+
     #[test]
     fn object_is_created_from_entity_error() {
         let messages = Messages::from_entity_error("EE");
@@ -434,4 +829,52 @@ mod tests {
         let output = format!("{messages:?}");
         assert_eq!("entity: [E: EE, E: EE]", output);
     }
+
+    #[test]
+    fn add_service_warning_works() {
+        let messages = Messages::new();
+        messages.add_service_warning("SW");
+        assert!(!messages.error());
+        assert!(messages.warning());
+        let output = format!("{messages:?}");
+        assert_eq!("service: [W: SW]", output);
+    }
+
+    #[test]
+    fn add_unique_skips_duplicates() {
+        let messages = Messages::new();
+        messages.add_unique(Messages::ENTITY, MessageType::Error, "EE");
+        messages.add_unique(Messages::ENTITY, MessageType::Error, "EE");
+        messages.add_unique(Messages::ENTITY, MessageType::Warning, "EE");
+        let output = format!("{messages:?}");
+        assert_eq!("entity: [E: EE, W: EE]", output);
+    }
+
+    #[test]
+    fn named_parameters_are_localized() {
+        let message = Message::new(MessageType::Error, "{count} of {0} in {name}")
+            .with_parameters(["10"])
+            .with_named_parameters([("name".into(), "list".into()), ("count".into(), "2".into())]);
+        let localized = message.localize(|text| text.to_smolstr());
+        assert_eq!("2 of 10 in list", localized.text());
+    }
+
+    #[test]
+    fn try_localize_works() {
+        let translate = |text: &str| match text {
+            "EE {0}" => Ok(SmolStr::from("entity error {0}")),
+            _ => Err(format_smolstr!("missing: {text}")),
+        };
+
+        let messages = Messages::from_entity_error_with_pars("EE {0}", ["X"]);
+        let output = format!("{:?}", messages.try_localize(translate).unwrap());
+        assert_eq!("entity: [E: entity error X]", output);
+
+        let messages = Messages::from_entity_error("EE {0}");
+        messages.add_service_error("SE");
+        assert_eq!(
+            Some(SmolStr::from("missing: SE")),
+            messages.try_localize(translate).err()
+        );
+    }
 }
@@ -1,5 +1,8 @@
 use std::ops::{Deref, DerefMut};
 
+#[cfg(feature = "derive")]
+pub use fetsig_derive::{Dirty, New};
+
 pub trait Inner<E>
 where
     Self: Deref<Target = E> + DerefMut,
@@ -43,3 +46,35 @@ pub trait Dirty {
 
     fn mark_as_dirty(&mut self);
 }
+
+#[cfg(all(test, feature = "derive"))]
+mod tests {
+    use super::*;
+
+    #[derive(Default, Dirty, New)]
+    struct Entity {
+        name: String,
+        #[dirty]
+        changed: bool,
+        is_new: bool,
+    }
+
+    #[test]
+    fn derived_dirty() {
+        let mut entity = Entity::default().with_dirty();
+        assert!(entity.is_dirty());
+        assert!(entity.take_dirty());
+        assert!(!entity.is_dirty());
+        assert!(!entity.take_dirty());
+        entity.mark_as_dirty();
+        assert!(entity.changed);
+        assert!(entity.name.is_empty());
+    }
+
+    #[test]
+    fn derived_new() {
+        let entity = Entity::default().with_new();
+        assert!(entity.is_new());
+        assert!(!entity.with_existing().is_new());
+    }
+}
@@ -5,6 +5,7 @@ mod json {
     use std::io::Write;
 
     use serde::{Serialize, de::DeserializeOwned};
+    use serde_json::{Serializer, ser::Formatter};
     use smol_str::SmolStr;
 
     use crate::uformat_smolstr;
@@ -23,6 +24,23 @@ mod json {
             self.write_json(&mut buffer)?;
             Ok(buffer)
         }
+
+        // formatter decides how numbers, strings etc. are written, e.g. big integers as strings
+        fn write_json_with<W: Write, F: Formatter>(
+            &self,
+            writer: &mut W,
+            formatter: F,
+        ) -> Result<(), SmolStr> {
+            let mut serializer = Serializer::with_formatter(writer, formatter);
+            self.serialize(&mut serializer)
+                .map_err(|e| uformat_smolstr!("Serialization (json) failed: {}", e.to_string()))
+        }
+
+        fn to_json_with<F: Formatter>(&self, formatter: F) -> Result<Vec<u8>, SmolStr> {
+            let mut buffer = Vec::with_capacity(8192);
+            self.write_json_with(&mut buffer, formatter)?;
+            Ok(buffer)
+        }
     }
 
     pub trait JSONDeserialize
@@ -37,6 +55,61 @@ mod json {
 
     impl<E> JSONSerialize for E where E: Serialize {}
     impl<E> JSONDeserialize for E where E: DeserializeOwned {}
+
+    #[cfg(test)]
+    mod tests {
+        use std::io;
+
+        use serde::Serialize;
+        use serde_json::ser::{CompactFormatter, Formatter};
+
+        use super::*;
+
+        const MAX_SAFE_INTEGER: u64 = (1 << 53) - 1;
+
+        struct SafeIntegers;
+
+        impl Formatter for SafeIntegers {
+            fn write_u64<W: ?Sized + io::Write>(
+                &mut self,
+                writer: &mut W,
+                value: u64,
+            ) -> io::Result<()> {
+                if value > MAX_SAFE_INTEGER {
+                    write!(writer, "\"{value}\"")
+                } else {
+                    CompactFormatter.write_u64(writer, value)
+                }
+            }
+        }
+
+        #[derive(Serialize)]
+        struct Item {
+            id: u64,
+            count: u64,
+        }
+
+        #[test]
+        fn default_formatter_matches_to_json() {
+            let item = Item { id: 1, count: 2 };
+            assert_eq!(
+                item.to_json().unwrap(),
+                item.to_json_with(CompactFormatter).unwrap()
+            );
+        }
+
+        #[test]
+        fn custom_formatter() {
+            let item = Item {
+                id: u64::MAX,
+                count: 2,
+            };
+            assert_eq!(
+                br#"{"id":"18446744073709551615","count":2}"#.as_slice(),
+                item.to_json_with(SafeIntegers).unwrap()
+            );
+        }
+    }
 }
 
 #[cfg(feature = "postcard")]
@@ -126,3 +199,120 @@ mod postcard {
     impl<E> PostcardSerialize for E where E: Serialize {}
     impl<E> PostcardDeserialize for E where E: DeserializeOwned {}
 }
+
+#[cfg(feature = "cbor")]
+pub use cbor::*;
+#[cfg(feature = "cbor")]
+mod cbor {
+    use std::io::Write;
+
+    use serde::{Serialize, de::DeserializeOwned};
+    use smol_str::SmolStr;
+
+    use crate::uformat_smolstr;
+
+    pub trait CborSerialize
+    where
+        Self: Serialize,
+    {
+        fn write_cbor<W: Write>(&self, writer: &mut W) -> Result<(), SmolStr> {
+            ciborium::into_writer(self, writer)
+                .map_err(|e| uformat_smolstr!("Serialization (cbor) failed: {}", e.to_string()))
+        }
+
+        fn to_cbor(&self) -> Result<Vec<u8>, SmolStr> {
+            let mut buffer = Vec::with_capacity(4096);
+            self.write_cbor(&mut buffer)?;
+            Ok(buffer)
+        }
+    }
+
+    pub trait CborDeserialize
+    where
+        Self: DeserializeOwned,
+    {
+        fn try_from_cbor(cbor: &[u8]) -> Result<Self, SmolStr> {
+            ciborium::from_reader::<Self, _>(cbor)
+                .map_err(|e| uformat_smolstr!("Deserialization (cbor) failed: {}", e.to_string()))
+        }
+    }
+
+    impl<E> CborSerialize for E where E: Serialize {}
+    impl<E> CborDeserialize for E where E: DeserializeOwned {}
+}
+
+#[cfg(feature = "messagepack")]
+pub use messagepack::*;
+#[cfg(feature = "messagepack")]
+mod messagepack {
+    use std::io::Write;
+
+    use serde::{Serialize, de::DeserializeOwned};
+    use smol_str::SmolStr;
+
+    use crate::uformat_smolstr;
+
+    pub trait MsgPackSerialize
+    where
+        Self: Serialize,
+    {
+        // structs are written as maps to stay readable by other MessagePack implementations
+        fn write_msgpack<W: Write>(&self, writer: &mut W) -> Result<(), SmolStr> {
+            rmp_serde::encode::write_named(writer, self).map_err(|e| {
+                uformat_smolstr!("Serialization (messagepack) failed: {}", e.to_string())
+            })
+        }
+
+        fn to_msgpack(&self) -> Result<Vec<u8>, SmolStr> {
+            let mut buffer = Vec::with_capacity(4096);
+            self.write_msgpack(&mut buffer)?;
+            Ok(buffer)
+        }
+    }
+
+    pub trait MsgPackDeserialize
+    where
+        Self: DeserializeOwned,
+    {
+        fn try_from_msgpack(msgpack: &[u8]) -> Result<Self, SmolStr> {
+            rmp_serde::from_slice::<Self>(msgpack).map_err(|e| {
+                uformat_smolstr!("Deserialization (messagepack) failed: {}", e.to_string())
+            })
+        }
+    }
+
+    impl<E> MsgPackSerialize for E where E: Serialize {}
+    impl<E> MsgPackDeserialize for E where E: DeserializeOwned {}
+
+    #[cfg(test)]
+    mod tests {
+        use serde::{Deserialize, Serialize};
+
+        use super::*;
+
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct Item {
+            id: u32,
+            name: String,
+            tags: Vec<String>,
+            parent: Option<u32>,
+        }
+
+        #[test]
+        fn round_trip() {
+            let item = Item {
+                id: 7,
+                name: "seven".into(),
+                tags: vec!["a".into(), "b".into()],
+                parent: None,
+            };
+            let bytes = item.to_msgpack().unwrap();
+            assert_eq!(item, Item::try_from_msgpack(&bytes).unwrap());
+        }
+
+        #[test]
+        fn invalid_is_rejected() {
+            assert!(Item::try_from_msgpack(&[0xc1]).is_err());
+        }
+    }
+}
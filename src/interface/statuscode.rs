@@ -1,3 +1,6 @@
+use core::fmt::Display;
+
+use serde::{Deserialize, Deserializer, Serialize};
 use ufmt::derive::uDebug;
 
 #[derive(Debug, uDebug, Clone, Copy, PartialEq, Eq)]
@@ -7,32 +10,58 @@ pub enum StatusCode {
     FetchFailed = 901,
     FetchTimeout = 902,
     DecodeFailed = 903,
+    Aborted = 904,
 
     Ok = 200,
     Created = 201,
+    Accepted = 202,
     NoContent = 204,
+    PartialContent = 206,
 
+    MovedPermanently = 301,
+    Found = 302,
     NotModified = 304,
+    TemporaryRedirect = 307,
+    PermanentRedirect = 308,
 
     BadRequest = 400,
     Unauthorized = 401,
     Forbidden = 403,
     NotFound = 404,
     MethodNotAllowed = 405,
+    RequestTimeout = 408,
     Conflict = 409,
     PayloadTooBig = 413,
     UnsupportedMediaType = 415,
+    UnprocessableEntity = 422,
     RateLimited = 429,
 
     InternalServerError = 500,
     NotImplemented = 501,
+    ServiceUnavailable = 503,
+    GatewayTimeout = 504,
 }
 
 impl StatusCode {
+    pub fn as_u16(&self) -> u16 {
+        *self as u16
+    }
+
+    #[cfg(feature = "browser")]
+    #[inline]
+    pub fn from_response(response: &web_sys::Response) -> Self {
+        Self::from(response)
+    }
+
     pub fn is_success(&self) -> bool {
         matches!(
             self,
-            Self::Ok | Self::Created | Self::NoContent | Self::NotModified
+            Self::Ok
+                | Self::Created
+                | Self::Accepted
+                | Self::NoContent
+                | Self::PartialContent
+                | Self::NotModified
         )
     }
 
@@ -41,7 +70,44 @@ impl StatusCode {
     }
 
     pub fn is_local(&self) -> bool {
-        matches!(self, Self::FetchFailed | Self::FetchTimeout)
+        matches!(self, Self::FetchFailed | Self::FetchTimeout | Self::Aborted)
+    }
+
+    pub fn is_redirect(&self) -> bool {
+        matches!(
+            self,
+            Self::MovedPermanently
+                | Self::Found
+                | Self::TemporaryRedirect
+                | Self::PermanentRedirect
+        )
+    }
+
+    pub fn is_client_error(&self) -> bool {
+        (400..500).contains(&self.as_u16())
+    }
+
+    pub fn is_server_error(&self) -> bool {
+        (500..600).contains(&self.as_u16())
+    }
+
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            Self::FetchTimeout
+                | Self::FetchFailed
+                | Self::RequestTimeout
+                | Self::RateLimited
+                | Self::InternalServerError
+                | Self::ServiceUnavailable
+                | Self::GatewayTimeout
+        )
+    }
+}
+
+impl Display for StatusCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_u16())
     }
 }
 
@@ -60,23 +126,146 @@ impl From<u16> for StatusCode {
         match code {
             200 => Self::Ok,
             201 => Self::Created,
+            202 => Self::Accepted,
             204 => Self::NoContent,
+            206 => Self::PartialContent,
+            301 => Self::MovedPermanently,
+            302 => Self::Found,
             304 => Self::NotModified,
+            307 => Self::TemporaryRedirect,
+            308 => Self::PermanentRedirect,
             400 => Self::BadRequest,
             401 => Self::Unauthorized,
             403 => Self::Forbidden,
             404 => Self::NotFound,
             405 => Self::MethodNotAllowed,
+            408 => Self::RequestTimeout,
             409 => Self::Conflict,
             413 => Self::PayloadTooBig,
             415 => Self::UnsupportedMediaType,
+            422 => Self::UnprocessableEntity,
             429 => Self::RateLimited,
             500 => Self::InternalServerError,
             501 => Self::NotImplemented,
+            503 => Self::ServiceUnavailable,
+            504 => Self::GatewayTimeout,
             901 => Self::FetchFailed,
             902 => Self::FetchTimeout,
             903 => Self::DecodeFailed,
+            904 => Self::Aborted,
             _ => Self::Undefined,
         }
     }
 }
+
+impl Serialize for StatusCode {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_u16(self.as_u16())
+    }
+}
+
+impl<'de> Deserialize<'de> for StatusCode {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let code = <u16 as Deserialize>::deserialize(deserializer)?;
+        Ok(code.into())
+    }
+}
+
+#[cfg(feature = "browser")]
+impl From<&web_sys::Response> for StatusCode {
+    fn from(response: &web_sys::Response) -> Self {
+        Self::from(response.status())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn client_error_boundaries() {
+        assert!(!StatusCode::NotModified.is_client_error());
+        assert!(StatusCode::BadRequest.is_client_error());
+        assert!(StatusCode::RateLimited.is_client_error());
+        assert!(!StatusCode::InternalServerError.is_client_error());
+        assert!(!StatusCode::FetchFailed.is_client_error());
+    }
+
+    #[test]
+    fn server_error_boundaries() {
+        assert!(!StatusCode::RateLimited.is_server_error());
+        assert!(StatusCode::InternalServerError.is_server_error());
+        assert!(StatusCode::ServiceUnavailable.is_server_error());
+        assert!(!StatusCode::Undefined.is_server_error());
+        assert!(!StatusCode::FetchTimeout.is_server_error());
+    }
+
+    #[test]
+    fn retryable_statuses() {
+        assert!(StatusCode::FetchTimeout.is_retryable());
+        assert!(StatusCode::FetchFailed.is_retryable());
+        assert!(StatusCode::RateLimited.is_retryable());
+        assert!(StatusCode::InternalServerError.is_retryable());
+        assert!(StatusCode::ServiceUnavailable.is_retryable());
+        assert!(!StatusCode::DecodeFailed.is_retryable());
+        assert!(!StatusCode::Aborted.is_retryable());
+        assert!(!StatusCode::BadRequest.is_retryable());
+        assert!(!StatusCode::NotImplemented.is_retryable());
+    }
+
+    #[test]
+    fn local_is_unchanged() {
+        assert!(StatusCode::FetchFailed.is_local());
+        assert!(StatusCode::FetchTimeout.is_local());
+        assert!(StatusCode::Aborted.is_local());
+        assert!(!StatusCode::DecodeFailed.is_local());
+        assert!(!StatusCode::ServiceUnavailable.is_local());
+    }
+
+    #[test]
+    fn service_unavailable_is_mapped() {
+        assert_eq!(StatusCode::ServiceUnavailable, StatusCode::from(503));
+        assert_eq!(503, StatusCode::ServiceUnavailable.as_u16());
+    }
+
+    #[test]
+    fn redirects_are_mapped() {
+        for code in [301, 302, 307, 308] {
+            let status = StatusCode::from(code);
+            assert!(status.is_redirect());
+            assert_eq!(code, status.as_u16());
+        }
+        assert!(!StatusCode::NotModified.is_redirect());
+    }
+
+    #[test]
+    fn added_statuses_are_mapped() {
+        for code in [202, 206, 408, 422, 503, 504, 904] {
+            assert_eq!(code, StatusCode::from(code).as_u16());
+        }
+        assert!(StatusCode::Accepted.is_success());
+        assert!(StatusCode::UnprocessableEntity.is_client_error());
+        assert!(StatusCode::GatewayTimeout.is_retryable());
+        assert_eq!(StatusCode::Undefined, StatusCode::from(418));
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn serde_as_number() {
+        assert_eq!("404", serde_json::to_string(&StatusCode::NotFound).unwrap());
+        assert_eq!(
+            StatusCode::Conflict,
+            serde_json::from_str::<StatusCode>("409").unwrap()
+        );
+        assert_eq!(
+            StatusCode::Undefined,
+            serde_json::from_str::<StatusCode>("418").unwrap()
+        );
+    }
+}
@@ -78,6 +78,148 @@ impl<E> CollectionResponse<E> {
     }
 }
 
+impl<E> From<Vec<E>> for CollectionResponse<E> {
+    fn from(collection: Vec<E>) -> Self {
+        Self::new(Messages::new()).with_collection(collection)
+    }
+}
+
+// other envelopes are supported by implementing `From` for `EntityResponse`
+#[cfg_attr(
+    all(feature = "json", not(feature = "postcard")),
+    skip_serializing_none
+)]
+#[derive(Serialize, Deserialize)]
+pub struct EnvelopeResponse<E, M = EnvelopeMeta> {
+    data: Option<E>,
+    meta: Option<M>,
+}
+
+impl<E, M> EnvelopeResponse<E, M> {
+    pub fn new() -> Self {
+        Self {
+            data: None,
+            meta: None,
+        }
+    }
+
+    #[must_use]
+    pub fn with_data(mut self, data: E) -> Self {
+        self.data = Some(data);
+        self
+    }
+
+    #[must_use]
+    pub fn with_meta(mut self, meta: M) -> Self {
+        self.meta = Some(meta);
+        self
+    }
+
+    pub fn take(self) -> (Option<E>, Option<M>) {
+        (self.data, self.meta)
+    }
+}
+
+impl<E, M> Default for EnvelopeResponse<E, M> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<E, M> From<EnvelopeResponse<E, M>> for EntityResponse<E>
+where
+    M: Into<Messages>,
+{
+    fn from(envelope: EnvelopeResponse<E, M>) -> Self {
+        let messages = envelope.meta.map(Into::into).unwrap_or_default();
+        Self {
+            messages: messages.into_inner(),
+            entity: envelope.data,
+        }
+    }
+}
+
+#[cfg_attr(
+    all(feature = "json", not(feature = "postcard")),
+    skip_serializing_none
+)]
+#[derive(Default, Serialize, Deserialize)]
+pub struct EnvelopeMeta {
+    #[serde(default)]
+    messages: BTreeMap<SmolStr, MutableVec<Message>>,
+    paging: Option<Paging>,
+}
+
+impl EnvelopeMeta {
+    pub fn new(messages: Messages) -> Self {
+        Self {
+            messages: messages.into_inner(),
+            paging: None,
+        }
+    }
+
+    #[must_use]
+    pub fn with_paging(mut self, paging: Paging) -> Self {
+        self.paging = Some(paging);
+        self
+    }
+
+    pub fn take(self) -> (Messages, Option<Paging>) {
+        (Messages::from_inner(self.messages), self.paging)
+    }
+}
+
+impl From<EnvelopeMeta> for Messages {
+    fn from(meta: EnvelopeMeta) -> Self {
+        Messages::from_inner(meta.messages)
+    }
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct BatchRequest {
+    entities: BTreeMap<SmolStr, Vec<u8>>,
+}
+
+impl BatchRequest {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[must_use]
+    pub fn with_entity(mut self, key: SmolStr, entity: Vec<u8>) -> Self {
+        self.entities.insert(key, entity);
+        self
+    }
+
+    pub fn take(self) -> BTreeMap<SmolStr, Vec<u8>> {
+        self.entities
+    }
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct BatchResponse {
+    messages: BTreeMap<SmolStr, BTreeMap<SmolStr, MutableVec<Message>>>,
+}
+
+impl BatchResponse {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[must_use]
+    pub fn with_messages(mut self, key: SmolStr, messages: Messages) -> Self {
+        self.messages.insert(key, messages.into_inner());
+        self
+    }
+
+    pub fn take(self) -> BTreeMap<SmolStr, Messages> {
+        self.messages
+            .into_iter()
+            .map(|(key, messages)| (key, Messages::from_inner(messages)))
+            .collect()
+    }
+}
+
 #[cfg_attr(
     all(feature = "json", not(feature = "postcard")),
     skip_serializing_none
@@ -98,3 +240,82 @@ impl Default for Paging {
         }
     }
 }
+
+impl Paging {
+    #[must_use]
+    pub fn with_limit(mut self, limit: usize) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    #[must_use]
+    pub fn with_prev(mut self, prev: Option<SmolStr>) -> Self {
+        self.prev = prev;
+        self
+    }
+
+    #[must_use]
+    pub fn with_next(mut self, next: Option<SmolStr>) -> Self {
+        self.next = next;
+        self
+    }
+
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    pub fn prev(&self) -> Option<&str> {
+        self.prev.as_deref()
+    }
+
+    pub fn next(&self) -> Option<&str> {
+        self.next.as_deref()
+    }
+
+    pub fn has_prev(&self) -> bool {
+        self.prev.is_some()
+    }
+
+    pub fn has_next(&self) -> bool {
+        self.next.is_some()
+    }
+}
+
+#[cfg(all(test, feature = "json"))]
+mod tests {
+    use crate::JSONDeserialize;
+
+    use super::*;
+
+    #[test]
+    fn envelope_into_entity_response() {
+        let json = br#"{
+            "data": 7,
+            "meta": {
+                "messages": {"entity": [{"message_type": "Warning", "text": "W", "parameters": []}]},
+                "paging": {"limit": 10}
+            }
+        }"#;
+        let envelope = EnvelopeResponse::<u32>::try_from_json(json).unwrap();
+        let (entity, messages) = EntityResponse::from(envelope).take();
+        assert_eq!(Some(7), entity);
+        assert_eq!("entity: [W: W]", format!("{messages:?}"));
+    }
+
+    #[test]
+    fn envelope_without_meta() {
+        let envelope = EnvelopeResponse::<u32>::try_from_json(br#"{"data":7}"#).unwrap();
+        let (entity, messages) = EntityResponse::from(envelope).take();
+        assert_eq!(Some(7), entity);
+        assert_eq!("", format!("{messages:?}"));
+    }
+
+    #[test]
+    fn bare_array_into_collection_response() {
+        let collection = Vec::<u32>::try_from_json(b"[1, 2]").unwrap();
+        let (collection, messages, paging) = CollectionResponse::from(collection).take();
+        assert_eq!(Some(vec![1, 2]), collection);
+        assert_eq!("", format!("{messages:?}"));
+        assert!(!paging.has_next());
+    }
+}
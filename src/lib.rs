@@ -1,12 +1,16 @@
-#[cfg(feature = "browser")]
+#[cfg(any(feature = "browser", feature = "native"))]
 mod browser;
-#[cfg(feature = "browser")]
+#[cfg(any(feature = "browser", feature = "native"))]
 pub use browser::*;
 
 mod interface;
 pub use interface::*;
 
 pub use futures_signals_ext::*;
+
+// derived impls refer to `::fetsig`
+#[cfg(all(test, feature = "derive"))]
+extern crate self as fetsig;
 use smol_str::SmolStrBuilder;
 
 use ufmt::uWrite;
@@ -32,7 +36,13 @@ impl uWrite for Ufmtf {
     }
 }
 
-#[cfg(all(feature = "browser", not(feature = "json"), not(feature = "postcard")))]
+#[cfg(all(
+    any(feature = "browser", feature = "native"),
+    not(feature = "cbor"),
+    not(feature = "json"),
+    not(feature = "messagepack"),
+    not(feature = "postcard")
+))]
 compile_error!(
-    "No serialization feature present, select at least one of 'json' or 'postcard' features."
+    "No serialization feature present, select at least one of 'cbor', 'json', 'messagepack' or 'postcard' features."
 );